@@ -1,23 +1,44 @@
 #![allow(dead_code)]
+mod bytecode;
 mod visitor;
 
+use super::cst;
 use super::lexer;
-pub use visitor::{AstEvaluator, AstNodeVisitor, AstPrinter};
+pub use bytecode::{BytecodeCompiler, Instr, Program, Vm};
+pub use visitor::{AstEvaluator, AstNodeVisitor, AstPrinter, AstSexpDumper, EvalError, Value};
 
 pub trait AstError: std::marker::Sized + std::fmt::Debug {
     type E;
 
     fn is_empty(&self) -> bool;
     fn get_string<'cu>(&self, ast: &'cu Ast<'cu, Self>) -> String;
+    // machine-readable rendering for `--error-format=json`; defaults to the human string wrapped in
+    // a single record so error slots that have no structured form still emit something parseable
+    fn get_json<'cu>(&self, ast: &'cu Ast<'cu, Self>) -> String {
+        let mut message = String::new();
+        for c in self.get_string(ast).chars() {
+            match c {
+                '"' => message.push_str("\\\""),
+                '\\' => message.push_str("\\\\"),
+                c if (c as u32) < 0x20 => message.push_str(&format!("\\u{:04x}", c as u32)),
+                c => message.push(c),
+            }
+        }
+        format!("{{\"severity\":\"error\",\"message\":\"{message}\",\"spans\":[],\"children\":[]}}")
+    }
 }
 
 #[derive(Debug)]
 pub struct Ast<'cu, Error: AstError> {
     cu: &'cu lexer::CompilationUnit,
     nodes: AstNodes, // last node is always a module
+    // the inclusive token range each node covers, trivia included, kept parallel to `nodes` so the
+    // concrete-syntax-tree layer can reproduce the original source (see `build_cst`)
+    node_spans: Vec<(lexer::TokenIdx, lexer::TokenIdx)>,
     tokens: lexer::Tokens,
     diag_ctx: lexer::DiagCtx<'cu>,
     error: Option<Error>,
+    error_buffer: Vec<Error>,
 }
 
 impl<'cu, E: AstError> Ast<'cu, E> {
@@ -27,9 +48,11 @@ impl<'cu, E: AstError> Ast<'cu, E> {
         Self {
             cu,
             nodes: AstNodes::with_capacity(tokens_len),
+            node_spans: Vec::with_capacity(tokens_len),
             tokens,
             diag_ctx,
             error: None,
+            error_buffer: Vec::new(),
         }
     }
     pub(crate) fn get_tokens(&self) -> &lexer::Tokens {
@@ -44,6 +67,9 @@ impl<'cu, E: AstError> Ast<'cu, E> {
     fn get_token(&self, token_idx: lexer::TokenIdx) -> Option<&lexer::Token> {
         self.tokens.get(token_idx)
     }
+    pub(crate) fn get_token_str(&self, token_idx: lexer::TokenIdx) -> &'cu str {
+        self.get_token_unchecked(token_idx).get_str(self.cu)
+    }
     fn get_token_unchecked(&self, token_idx: lexer::TokenIdx) -> &lexer::Token {
         self.tokens
             .get(token_idx)
@@ -74,33 +100,135 @@ impl<'cu, E: AstError> Ast<'cu, E> {
     pub fn get_diagnostics(&self) -> Option<String> {
         self.get_error().map(|error| error.get_string(self))
     }
-    pub fn get_diag_with_error_token(&self, error_token_idx: lexer::TokenIdx) -> String {
+    pub fn get_diagnostics_json(&self) -> Option<String> {
+        self.get_error().map(|error| error.get_json(self))
+    }
+    // the half-open byte range a token covers, trivia excluded
+    pub(crate) fn token_byte_range(&self, token_idx: lexer::TokenIdx) -> (usize, usize) {
+        self.get_token_unchecked(token_idx).get_byte_range(self.cu)
+    }
+    // the 1-based line and grapheme column at which a token begins
+    pub(crate) fn token_line_col(&self, token_idx: lexer::TokenIdx) -> (usize, usize) {
+        let (start, _) = self.token_byte_range(token_idx);
+        self.diag_ctx.line_col_of_byte(start)
+    }
+    pub(crate) fn get_diag_with_error_token(
+        &self,
+        error_token_idx: lexer::TokenIdx,
+        labels: lexer::SpanLabels,
+    ) -> lexer::Diagnostics {
         self.get_diag_ctx()
-            .get_diag_with_error_token(error_token_idx, &self.tokens, self.cu)
-            .to_string()
+            .get_diag_with_error_token(error_token_idx, labels, &self.tokens, self.cu)
     }
-    pub fn get_diag_with_ctx_token(&self, ctx_start_token_idx: lexer::TokenIdx) -> String {
+    pub(crate) fn get_diag_with_ctx_token(
+        &self,
+        ctx_start_token_idx: lexer::TokenIdx,
+        labels: lexer::SpanLabels,
+    ) -> lexer::Diagnostics {
         self.get_diag_ctx()
-            .get_diag_with_ctx_token(ctx_start_token_idx, &self.tokens, self.cu)
-            .to_string()
+            .get_diag_with_ctx_token(ctx_start_token_idx, labels, &self.tokens, self.cu)
     }
-    pub fn get_diag_with_ctx_and_error_tokens(
+    pub(crate) fn get_diag_with_ctx_and_error_tokens(
         &self,
         ctx_start_token_idx: lexer::TokenIdx,
         error_token_idx: lexer::TokenIdx,
-    ) -> String {
-        self.get_diag_ctx()
-            .get_diag_with_ctx_and_error_tokens(
-                ctx_start_token_idx,
-                error_token_idx,
-                &self.tokens,
-                self.cu,
-            )
-            .to_string()
+        labels: lexer::SpanLabels,
+    ) -> lexer::Diagnostics {
+        self.get_diag_ctx().get_diag_with_ctx_and_error_tokens(
+            ctx_start_token_idx,
+            error_token_idx,
+            labels,
+            &self.tokens,
+            self.cu,
+        )
     }
     pub(crate) fn push_node(&mut self, node: AstNode) -> AstNodeIdx {
+        // record the inclusive token range this node covers before handing the node to the arena.
+        // because the arena is built bottom-up, every child node was pushed earlier and already has
+        // a recorded span, so merging the node's own tokens with its children's spans yields the
+        // full range — including the interleaved trivia tokens that sit between them.
+        let span = self.covering_span(&node);
+        self.node_spans.push(span);
         self.nodes.push(node)
     }
+
+    // the smallest inclusive token range covering a node's own tokens and all of its descendants
+    fn covering_span(&self, node: &AstNode) -> (lexer::TokenIdx, lexer::TokenIdx) {
+        // a module owns the whole compilation unit, so it stretches over every token — including
+        // the blanks and comments that lead or trail the file and belong to no statement
+        if let AstNode::Module { .. } = node {
+            let last = self.tokens.len().saturating_sub(1);
+            return (lexer::TokenIdx::new(0), lexer::TokenIdx::new(last));
+        }
+        let (tokens, children) = node_refs(node);
+        let mut bounds: Option<(lexer::TokenIdx, lexer::TokenIdx)> = None;
+        let ranges = tokens
+            .into_iter()
+            .map(|token_idx| (token_idx, token_idx))
+            .chain(children.into_iter().map(|child_idx| self.node_spans[child_idx.get()]));
+        for (lo, hi) in ranges {
+            bounds = Some(match bounds {
+                Some((l, h)) => (l.min(lo), h.max(hi)),
+                None => (lo, hi),
+            });
+        }
+        bounds.unwrap_or_else(|| panic!("BUG: node `{:?}` covers no tokens", node))
+    }
+
+    // reconstructs the lossless concrete-syntax-tree rooted at the module node; every construct is
+    // rebuilt from its covering token range so the blanks and comments between children are
+    // retained and the original source can be reproduced byte-for-byte
+    pub fn build_cst(&self) -> cst::Node {
+        // an empty compilation unit has no tokens to anchor on, so the module spans nothing
+        if self.tokens.get(lexer::TokenIdx::new(0)).is_none() {
+            return cst::Node::new(cst::GreenNode::new(cst::SyntaxKind::Module, vec![]), 0);
+        }
+        let root_idx = AstNodeIdx::new(self.nodes.len() - 1);
+        let (first_token_idx, _) = self.node_spans[root_idx.get()];
+        let offset = self.get_token_unchecked(first_token_idx).get_byte_range(self.cu).0;
+        cst::Node::new(self.build_green(root_idx), offset)
+    }
+
+    fn build_green(&self, node_idx: AstNodeIdx) -> std::rc::Rc<cst::GreenNode> {
+        let node = self.get_node_unchecked(node_idx);
+        let (lo, hi) = self.node_spans[node_idx.get()];
+        // children in source order so the walk can splice each subtree in where it starts, treating
+        // every token between them as a leaf (trivia or otherwise)
+        let (_, child_indices) = node_refs(node);
+        let mut children: Vec<(lexer::TokenIdx, lexer::TokenIdx, AstNodeIdx)> = child_indices
+            .into_iter()
+            .map(|child_idx| {
+                let (child_lo, child_hi) = self.node_spans[child_idx.get()];
+                (child_lo, child_hi, child_idx)
+            })
+            .collect();
+        children.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut green_children = Vec::new();
+        let mut cursor = 0;
+        let mut token_idx = lo;
+        while token_idx <= hi {
+            if cursor < children.len() && children[cursor].0 == token_idx {
+                let (_, child_hi, child_idx) = children[cursor];
+                cursor += 1;
+                green_children.push(cst::GreenChild::Node(self.build_green(child_idx)));
+                token_idx = child_hi + 1;
+                continue;
+            }
+            let token = self.get_token_unchecked(token_idx);
+            let kind = if is_trivia(token.get_kind()) {
+                cst::SyntaxKind::Trivia
+            } else {
+                cst::SyntaxKind::Token
+            };
+            green_children.push(cst::GreenChild::Token(cst::GreenToken::new(
+                kind,
+                self.get_token_str(token_idx).to_owned(),
+            )));
+            token_idx += 1;
+        }
+        cst::GreenNode::new(cst_kind(node), green_children)
+    }
     pub(crate) fn set_module(&mut self, module: AstNode) {
         match module {
             AstNode::Module { .. } => {
@@ -117,6 +245,16 @@ impl<'cu, E: AstError> Ast<'cu, E> {
     pub(crate) fn get_error(&self) -> Option<&E> {
         self.error.as_ref()
     }
+    // records a diagnostic discovered during error recovery; the buffered errors are later folded
+    // into the ast's error so a single parse reports every problem it found
+    pub(crate) fn accumulate_error(&mut self, error: E) {
+        if !error.is_empty() {
+            self.error_buffer.push(error);
+        }
+    }
+    pub(crate) fn take_accumulated_errors(&mut self) -> Vec<E> {
+        std::mem::take(&mut self.error_buffer)
+    }
 }
 
 impl<'cu, Error: AstError> std::fmt::Display for Ast<'cu, Error> {
@@ -176,6 +314,7 @@ pub enum AstNode {
     Statement(Stat),
     Expression(Expr),
     Annotation(Anno),
+    Pattern(Pat),
 }
 
 impl AstNode {
@@ -209,9 +348,232 @@ impl AstNode {
     }
 }
 
+/// Parses the numeric part of an integer literal and range-checks it against its declared
+/// bit-width and signedness, returning the value as an `i64` for the runtime value model.
+pub(crate) fn parse_int_literal(text: &str, bits: u32, signed: bool) -> Result<i64, String> {
+    let digits: String = text.chars().take_while(|c| c.is_ascii_digit()).collect();
+    let value: i128 = digits
+        .parse()
+        .map_err(|e| format!("failed to parse integer `{}`: {}", text, e))?;
+    let max: i128 = if signed {
+        (1i128 << (bits - 1)) - 1
+    } else {
+        (1i128 << bits) - 1
+    };
+    if value > max {
+        return Err(format!("literal `{}` does not fit in its type", text));
+    }
+    i64::try_from(value).map_err(|_| format!("literal `{}` does not fit in an i64", text))
+}
+
+// the tokens a node references directly and the child nodes it owns; the covering-span and
+// concrete-syntax-tree walks share this one enumeration so the two never drift apart
+fn node_refs(node: &AstNode) -> (Vec<lexer::TokenIdx>, Vec<AstNodeIdx>) {
+    match node {
+        AstNode::Module {
+            statements_node_indices,
+        } => (vec![], statements_node_indices.clone()),
+        AstNode::Statement(Stat::Expression(idx)) => (vec![], vec![*idx]),
+        AstNode::Statement(Stat::Definition {
+            kw,
+            lhs_expression_node_idx,
+            colon,
+            type_node_idx,
+            eq,
+            rhs_expression_node_idx,
+        }) => {
+            let mut tokens = vec![*kw, *eq];
+            tokens.extend(colon.iter().copied());
+            let mut children = vec![*lhs_expression_node_idx];
+            children.extend(type_node_idx.iter().copied());
+            children.push(*rhs_expression_node_idx);
+            (tokens, children)
+        }
+        AstNode::Expression(expr) => match expr {
+            Expr::Int { token_idx, .. } => (vec![*token_idx], vec![]),
+            Expr::Identifier(token_idx) => (vec![*token_idx], vec![]),
+            Expr::StringLiteral { token_idx, .. } => (vec![*token_idx], vec![]),
+            Expr::If {
+                if_kw,
+                condition_node_idx,
+                then_block_node_idx,
+                else_kw,
+                else_block_node_idx,
+                if_node_idx,
+            } => {
+                let mut tokens = vec![*if_kw];
+                tokens.extend(else_kw.iter().copied());
+                let mut children = vec![*condition_node_idx, *then_block_node_idx];
+                children.extend(else_block_node_idx.iter().copied());
+                children.extend(if_node_idx.iter().copied());
+                (tokens, children)
+            }
+            Expr::ArithmeticOrLogical { operator, lhs, rhs } => {
+                (vec![*operator], vec![*lhs, *rhs])
+            }
+            Expr::Assign { target, eq, value } => (vec![*eq], vec![*target, *value]),
+            Expr::Negation { operator, operand } => (vec![*operator], vec![*operand]),
+            Expr::Not { operator, operand } => (vec![*operator], vec![*operand]),
+            Expr::Grouped {
+                lparen,
+                expression_node_idx,
+                rparen,
+            } => (vec![*lparen, *rparen], vec![*expression_node_idx]),
+            Expr::Call {
+                callee,
+                lparen,
+                args,
+                rparen,
+            } => {
+                let mut children = vec![*callee];
+                children.extend(args.iter().copied());
+                (vec![*lparen, *rparen], children)
+            }
+            Expr::Index {
+                base,
+                lbracket,
+                index,
+                rbracket,
+            } => (vec![*lbracket, *rbracket], vec![*base, *index]),
+            Expr::Block {
+                lcurlybracket,
+                statements_node_indices,
+                tail_expression_node_idx,
+                rcurlybracket,
+            } => {
+                let mut children = statements_node_indices.clone();
+                children.extend(tail_expression_node_idx.iter().copied());
+                (vec![*lcurlybracket, *rcurlybracket], children)
+            }
+            Expr::Return {
+                return_kw,
+                expression_node_idx,
+            } => (
+                vec![*return_kw],
+                expression_node_idx.iter().copied().collect(),
+            ),
+            Expr::While {
+                while_kw,
+                condition_node_idx,
+                body_block_node_idx,
+            } => (
+                vec![*while_kw],
+                vec![*condition_node_idx, *body_block_node_idx],
+            ),
+            Expr::Loop {
+                loop_kw,
+                body_block_node_idx,
+            } => (vec![*loop_kw], vec![*body_block_node_idx]),
+            Expr::Break {
+                break_kw,
+                expression_node_idx,
+            } => (
+                vec![*break_kw],
+                expression_node_idx.iter().copied().collect(),
+            ),
+            Expr::Continue { continue_kw } => (vec![*continue_kw], vec![]),
+            Expr::Match {
+                match_kw,
+                scrutinee_node_idx,
+                arms,
+            } => {
+                let mut tokens = vec![*match_kw];
+                let mut children = vec![*scrutinee_node_idx];
+                for arm in arms {
+                    tokens.push(arm.fat_arrow);
+                    children.push(arm.pattern_node_idx);
+                    children.push(arm.body_node_idx);
+                }
+                (tokens, children)
+            }
+            Expr::Let {
+                pattern_node_idx,
+                eq_token,
+                value_node_idx,
+            } => (vec![*eq_token], vec![*pattern_node_idx, *value_node_idx]),
+            Expr::Error { span_token_idx } => (vec![*span_token_idx], vec![]),
+        },
+        AstNode::Annotation(anno) => match anno {
+            Anno::Type { token_idx } => (vec![*token_idx], vec![]),
+            Anno::Generic { head, args } => (vec![*head], args.clone()),
+            Anno::Tuple { elems } => (vec![], elems.clone()),
+            Anno::Func { fn_kw, params, ret } => {
+                let mut children = params.clone();
+                children.push(*ret);
+                (vec![*fn_kw], children)
+            }
+        },
+        AstNode::Pattern(pat) => match pat {
+            Pat::Wildcard { token_idx }
+            | Pat::Binding { token_idx }
+            | Pat::Int { token_idx }
+            | Pat::StringLiteral { token_idx, .. } => (vec![*token_idx], vec![]),
+            Pat::Tuple {
+                lparen,
+                elems,
+                rparen,
+            } => (vec![*lparen, *rparen], elems.clone()),
+        },
+    }
+}
+
+// whether a token is blank or a comment, i.e. a leaf the abstract tree skips but the concrete tree
+// retains as [`cst::SyntaxKind::Trivia`]
+fn is_trivia(kind: &lexer::TokenKind) -> bool {
+    matches!(
+        kind,
+        lexer::TokenKind::Spaces { .. } | lexer::TokenKind::NewLine | lexer::TokenKind::Comment
+    )
+}
+
+// the concrete-syntax-tree category matching an abstract node's variant
+fn cst_kind(node: &AstNode) -> cst::SyntaxKind {
+    match node {
+        AstNode::Module { .. } => cst::SyntaxKind::Module,
+        AstNode::Statement(Stat::Expression(..)) => cst::SyntaxKind::ExprStatement,
+        AstNode::Statement(Stat::Definition { .. }) => cst::SyntaxKind::Definition,
+        AstNode::Expression(expr) => match expr {
+            Expr::Int { .. } => cst::SyntaxKind::Int,
+            Expr::Identifier(..) => cst::SyntaxKind::Identifier,
+            Expr::StringLiteral { .. } => cst::SyntaxKind::StringLiteral,
+            Expr::If { .. } => cst::SyntaxKind::If,
+            Expr::ArithmeticOrLogical { .. } => cst::SyntaxKind::ArithmeticOrLogical,
+            Expr::Assign { .. } => cst::SyntaxKind::Assign,
+            Expr::Negation { .. } => cst::SyntaxKind::Negation,
+            Expr::Not { .. } => cst::SyntaxKind::Not,
+            Expr::Grouped { .. } => cst::SyntaxKind::Grouped,
+            Expr::Call { .. } => cst::SyntaxKind::Call,
+            Expr::Index { .. } => cst::SyntaxKind::Index,
+            Expr::Block { .. } => cst::SyntaxKind::Block,
+            Expr::Return { .. } => cst::SyntaxKind::Return,
+            Expr::While { .. } => cst::SyntaxKind::While,
+            Expr::Loop { .. } => cst::SyntaxKind::Loop,
+            Expr::Break { .. } => cst::SyntaxKind::Break,
+            Expr::Continue { .. } => cst::SyntaxKind::Continue,
+            Expr::Match { .. } => cst::SyntaxKind::Match,
+            Expr::Let { .. } => cst::SyntaxKind::Let,
+            Expr::Error { .. } => cst::SyntaxKind::Error,
+        },
+        AstNode::Annotation(anno) => match anno {
+            Anno::Type { .. } => cst::SyntaxKind::TypeAnno,
+            Anno::Generic { .. } => cst::SyntaxKind::GenericAnno,
+            Anno::Tuple { .. } => cst::SyntaxKind::TupleAnno,
+            Anno::Func { .. } => cst::SyntaxKind::FuncAnno,
+        },
+        AstNode::Pattern(pat) => match pat {
+            Pat::Tuple { .. } => cst::SyntaxKind::TuplePattern,
+            _ => cst::SyntaxKind::Pattern,
+        },
+    }
+}
+
 #[derive(Debug)]
 pub enum Expr {
-    I64(lexer::TokenIdx),
+    Int {
+        token_idx: lexer::TokenIdx,
+        bits: u32,
+        signed: bool,
+    },
     Identifier(lexer::TokenIdx),
     If {
         if_kw: lexer::TokenIdx,
@@ -230,24 +592,87 @@ pub enum Expr {
         lhs: AstNodeIdx,
         rhs: AstNodeIdx,
     },
+    Assign {
+        target: AstNodeIdx,
+        eq: lexer::TokenIdx,
+        value: AstNodeIdx,
+    },
     Negation {
         operator: lexer::TokenIdx,
         operand: AstNodeIdx,
     },
+    Not {
+        operator: lexer::TokenIdx,
+        operand: AstNodeIdx,
+    },
     Grouped {
         lparen: lexer::TokenIdx,
         expression_node_idx: AstNodeIdx,
         rparen: lexer::TokenIdx,
     },
+    Call {
+        callee: AstNodeIdx,
+        lparen: lexer::TokenIdx,
+        args: Vec<AstNodeIdx>,
+        rparen: lexer::TokenIdx,
+    },
+    Index {
+        base: AstNodeIdx,
+        lbracket: lexer::TokenIdx,
+        index: AstNodeIdx,
+        rbracket: lexer::TokenIdx,
+    },
     Block {
         lcurlybracket: lexer::TokenIdx,
         statements_node_indices: Vec<AstNodeIdx>,
+        tail_expression_node_idx: Option<AstNodeIdx>,
         rcurlybracket: lexer::TokenIdx,
     },
     Return {
         return_kw: lexer::TokenIdx,
         expression_node_idx: Option<AstNodeIdx>,
     },
+    While {
+        while_kw: lexer::TokenIdx,
+        condition_node_idx: AstNodeIdx,
+        body_block_node_idx: AstNodeIdx,
+    },
+    Loop {
+        loop_kw: lexer::TokenIdx,
+        body_block_node_idx: AstNodeIdx,
+    },
+    Break {
+        break_kw: lexer::TokenIdx,
+        expression_node_idx: Option<AstNodeIdx>,
+    },
+    Continue {
+        continue_kw: lexer::TokenIdx,
+    },
+    Match {
+        match_kw: lexer::TokenIdx,
+        scrutinee_node_idx: AstNodeIdx,
+        arms: Vec<MatchArm>,
+    },
+    // a `let PATTERN = EXPR` term, only valid directly in an `if`/`while` condition (possibly
+    // joined to other conditions with `&&`); the parser rejects it in any other position
+    Let {
+        pattern_node_idx: AstNodeIdx,
+        eq_token: lexer::TokenIdx,
+        value_node_idx: AstNodeIdx,
+    },
+    // a placeholder left behind by error recovery so parsing can continue past a broken
+    // expression and report the remaining diagnostics in the same pass
+    Error {
+        span_token_idx: lexer::TokenIdx,
+    },
+}
+
+/// A single `pattern => body` arm of a [`Expr::Match`].
+#[derive(Debug)]
+pub struct MatchArm {
+    pub pattern_node_idx: AstNodeIdx,
+    pub fat_arrow: lexer::TokenIdx,
+    pub body_node_idx: AstNodeIdx,
 }
 
 #[derive(Debug)]
@@ -265,7 +690,43 @@ pub enum Stat {
 
 #[derive(Debug)]
 pub enum Anno {
-    Type { token_idx: lexer::TokenIdx },
+    Type {
+        token_idx: lexer::TokenIdx,
+    },
+    Generic {
+        head: lexer::TokenIdx,
+        args: Vec<AstNodeIdx>,
+    },
+    Tuple {
+        elems: Vec<AstNodeIdx>,
+    },
+    Func {
+        fn_kw: lexer::TokenIdx,
+        params: Vec<AstNodeIdx>,
+        ret: AstNodeIdx,
+    },
+}
+
+#[derive(Debug)]
+pub enum Pat {
+    Wildcard {
+        token_idx: lexer::TokenIdx,
+    },
+    Binding {
+        token_idx: lexer::TokenIdx,
+    },
+    Int {
+        token_idx: lexer::TokenIdx,
+    },
+    StringLiteral {
+        token_idx: lexer::TokenIdx,
+        content: String,
+    },
+    Tuple {
+        lparen: lexer::TokenIdx,
+        elems: Vec<AstNodeIdx>,
+        rparen: lexer::TokenIdx,
+    },
 }
 
 #[derive(Debug)]
@@ -0,0 +1,481 @@
+use super::{Ast, AstError, AstNode, AstNodeIdx, AstNodeVisitor, Expr, Stat, Value};
+use crate::lexer;
+use std::collections::HashMap;
+
+/// A non-short-circuiting binary operator the stack machine understands.
+///
+/// This mirrors the arithmetic/comparison [`lexer::TokenKind`]s but is its own type so the public
+/// bytecode API does not leak the crate-internal lexer tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Pow,
+    Div,
+    Rem,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl BinOp {
+    // the arithmetic/comparison operators reach here already narrowed by the compiler; the
+    // short-circuiting `&&`/`||` are lowered to branches and never become a `BinOp`
+    fn from_token(kind: &lexer::TokenKind) -> Self {
+        match kind {
+            lexer::TokenKind::Plus => BinOp::Add,
+            lexer::TokenKind::Minus => BinOp::Sub,
+            lexer::TokenKind::Star => BinOp::Mul,
+            lexer::TokenKind::StarStar => BinOp::Pow,
+            lexer::TokenKind::Slash => BinOp::Div,
+            lexer::TokenKind::Percent => BinOp::Rem,
+            lexer::TokenKind::EqEq => BinOp::Eq,
+            lexer::TokenKind::Ne => BinOp::Ne,
+            lexer::TokenKind::Lt => BinOp::Lt,
+            lexer::TokenKind::Le => BinOp::Le,
+            lexer::TokenKind::Gt => BinOp::Gt,
+            lexer::TokenKind::Ge => BinOp::Ge,
+            other => panic!("BUG: `{:?}` is not a binary operator", other),
+        }
+    }
+}
+
+/// A single stack-machine instruction.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Instr {
+    PushI64(i64),
+    PushStr(usize),
+    PushBool(bool),
+    PushUnit,
+    Pop,
+    LoadLocal(usize),
+    StoreLocal(usize),
+    BinOp(BinOp),
+    Neg,
+    Not,
+    JumpIfFalse(usize),
+    Jump(usize),
+    Return,
+}
+
+/// A compiled program: a flat instruction stream plus the constants it refers to.
+#[derive(Debug, Clone)]
+pub struct Program {
+    code: Vec<Instr>,
+    strings: Vec<String>,
+    num_locals: usize,
+}
+
+/// Lowers an [`Ast`] into a [`Program`] for the stack [`Vm`].
+///
+/// Local bindings are resolved to integer slots at compile time so the runtime never performs a
+/// name lookup.
+#[derive(Debug)]
+pub struct BytecodeCompiler<'cu, E: AstError> {
+    ast: &'cu Ast<'cu, E>,
+    code: Vec<Instr>,
+    strings: Vec<String>,
+    // outermost-to-innermost slot scopes, the first being the module scope
+    scopes: Vec<HashMap<String, usize>>,
+    next_slot: usize,
+    max_slots: usize,
+}
+
+impl<'cu, E: AstError> BytecodeCompiler<'cu, E> {
+    pub fn new(ast: &'cu Ast<'cu, E>) -> Self {
+        Self {
+            ast,
+            code: Vec::new(),
+            strings: Vec::new(),
+            scopes: vec![HashMap::new()],
+            next_slot: 0,
+            max_slots: 0,
+        }
+    }
+
+    /// Consumes the compiler and returns the compiled [`Program`].
+    pub fn finish(self) -> Program {
+        Program {
+            code: self.code,
+            strings: self.strings,
+            num_locals: self.max_slots,
+        }
+    }
+
+    fn declare(&mut self, name: String) -> usize {
+        let slot = self.next_slot;
+        self.next_slot += 1;
+        self.max_slots = self.max_slots.max(self.next_slot);
+        self.scopes
+            .last_mut()
+            .expect("BUG: compiler must always have at least the module scope")
+            .insert(name, slot);
+        slot
+    }
+
+    fn resolve(&self, name: &str) -> Option<usize> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name).copied())
+    }
+
+    fn intern(&mut self, s: &str) -> usize {
+        if let Some(idx) = self.strings.iter().position(|existing| existing == s) {
+            idx
+        } else {
+            self.strings.push(s.to_owned());
+            self.strings.len() - 1
+        }
+    }
+
+    fn emit(&mut self, instr: Instr) -> usize {
+        self.code.push(instr);
+        self.code.len() - 1
+    }
+
+    // compiles statements in order, leaving the value of the last statement (or `Unit`) on the
+    // stack; `return` statements emit a `Return` that unwinds the whole program
+    fn compile_statements(
+        &mut self,
+        statements_node_indices: &[AstNodeIdx],
+    ) -> Result<(), String> {
+        if statements_node_indices.is_empty() {
+            self.emit(Instr::PushUnit);
+            return Ok(());
+        }
+
+        let last = statements_node_indices.len() - 1;
+        for (i, idx) in statements_node_indices.iter().enumerate() {
+            let is_last = i == last;
+            match self.ast.get_node_unchecked(*idx) {
+                AstNode::Statement(Stat::Definition {
+                    lhs_expression_node_idx,
+                    rhs_expression_node_idx,
+                    ..
+                }) => {
+                    self.visit(self.ast.get_node_unchecked(*rhs_expression_node_idx))?;
+                    let name = self.ast.get_string_unchecked(*lhs_expression_node_idx);
+                    let slot = self.declare(name);
+                    self.emit(Instr::StoreLocal(slot));
+                    if is_last {
+                        self.emit(Instr::PushUnit);
+                    }
+                }
+                AstNode::Statement(Stat::Expression(expression_node_idx)) => {
+                    self.visit(self.ast.get_node_unchecked(*expression_node_idx))?;
+                    if !is_last {
+                        self.emit(Instr::Pop);
+                    }
+                }
+                other => panic!("BUG: expected a statement, but got `{:?}`", other),
+            }
+        }
+        Ok(())
+    }
+
+    fn compile_if(
+        &mut self,
+        condition_node_idx: AstNodeIdx,
+        then_block_node_idx: AstNodeIdx,
+        else_block_node_idx: Option<AstNodeIdx>,
+        if_node_idx: Option<AstNodeIdx>,
+    ) -> Result<(), String> {
+        self.visit(self.ast.get_node_unchecked(condition_node_idx))?;
+        let jump_if_false = self.emit(Instr::JumpIfFalse(0));
+        self.visit(self.ast.get_node_unchecked(then_block_node_idx))?;
+        let jump_over_else = self.emit(Instr::Jump(0));
+
+        let else_label = self.code.len();
+        self.code[jump_if_false] = Instr::JumpIfFalse(else_label);
+        match (else_block_node_idx, if_node_idx) {
+            (Some(else_block_node_idx), _) => {
+                self.visit(self.ast.get_node_unchecked(else_block_node_idx))?;
+            }
+            (None, Some(if_node_idx)) => {
+                self.visit(self.ast.get_node_unchecked(if_node_idx))?;
+            }
+            (None, None) => {
+                self.emit(Instr::PushUnit);
+            }
+        }
+
+        let end_label = self.code.len();
+        self.code[jump_over_else] = Instr::Jump(end_label);
+        Ok(())
+    }
+}
+
+impl<'cu, E: AstError> AstNodeVisitor<Result<(), String>> for BytecodeCompiler<'cu, E> {
+    fn visit(&mut self, node: &AstNode) -> Result<(), String> {
+        match node {
+            AstNode::Module {
+                statements_node_indices,
+            } => self.compile_statements(statements_node_indices),
+            AstNode::Expression(Expr::Block {
+                statements_node_indices,
+                tail_expression_node_idx,
+                ..
+            }) => {
+                self.scopes.push(HashMap::new());
+                let saved_slot = self.next_slot;
+                let result = self.compile_statements(statements_node_indices).and_then(|()| {
+                    // the block's value is its trailing expression when present, so discard the
+                    // value left by the statements and leave the tail's value on the stack
+                    if let Some(idx) = tail_expression_node_idx {
+                        self.emit(Instr::Pop);
+                        self.visit(self.ast.get_node_unchecked(*idx))?;
+                    }
+                    Ok(())
+                });
+                self.next_slot = saved_slot;
+                self.scopes.pop();
+                result
+            }
+            AstNode::Expression(Expr::If {
+                condition_node_idx,
+                then_block_node_idx,
+                else_block_node_idx,
+                if_node_idx,
+                ..
+            }) => self.compile_if(
+                *condition_node_idx,
+                *then_block_node_idx,
+                *else_block_node_idx,
+                *if_node_idx,
+            ),
+            AstNode::Expression(Expr::Int {
+                token_idx,
+                bits,
+                signed,
+            }) => {
+                let value =
+                    super::parse_int_literal(self.ast.get_token_str(*token_idx), *bits, *signed)?;
+                self.emit(Instr::PushI64(value));
+                Ok(())
+            }
+            AstNode::Expression(Expr::StringLiteral { content, .. }) => {
+                let idx = self.intern(content);
+                self.emit(Instr::PushStr(idx));
+                Ok(())
+            }
+            AstNode::Expression(Expr::Identifier(token_idx)) => {
+                let name = self.ast.get_string_unchecked(*token_idx);
+                let slot = self
+                    .resolve(&name)
+                    .ok_or_else(|| format!("unbound name `{}`", name))?;
+                self.emit(Instr::LoadLocal(slot));
+                Ok(())
+            }
+            AstNode::Expression(Expr::ArithmeticOrLogical { operator, lhs, rhs }) => {
+                let op_kind = self.ast.get_token_unchecked(*operator).get_kind().clone();
+                match op_kind {
+                    // `&&` / `||` short-circuit, so they lower to branches rather than a `BinOp`
+                    lexer::TokenKind::AmpAmp => {
+                        self.visit(self.ast.get_node_unchecked(*lhs))?;
+                        let jump_if_false = self.emit(Instr::JumpIfFalse(0));
+                        self.visit(self.ast.get_node_unchecked(*rhs))?;
+                        let jump_over = self.emit(Instr::Jump(0));
+                        let false_label = self.code.len();
+                        self.code[jump_if_false] = Instr::JumpIfFalse(false_label);
+                        self.emit(Instr::PushBool(false));
+                        let end_label = self.code.len();
+                        self.code[jump_over] = Instr::Jump(end_label);
+                        Ok(())
+                    }
+                    lexer::TokenKind::PipePipe => {
+                        self.visit(self.ast.get_node_unchecked(*lhs))?;
+                        let jump_if_false = self.emit(Instr::JumpIfFalse(0));
+                        self.emit(Instr::PushBool(true));
+                        let jump_over = self.emit(Instr::Jump(0));
+                        let rhs_label = self.code.len();
+                        self.code[jump_if_false] = Instr::JumpIfFalse(rhs_label);
+                        self.visit(self.ast.get_node_unchecked(*rhs))?;
+                        let end_label = self.code.len();
+                        self.code[jump_over] = Instr::Jump(end_label);
+                        Ok(())
+                    }
+                    _ => {
+                        let op = BinOp::from_token(&op_kind);
+                        self.visit(self.ast.get_node_unchecked(*lhs))?;
+                        self.visit(self.ast.get_node_unchecked(*rhs))?;
+                        self.emit(Instr::BinOp(op));
+                        Ok(())
+                    }
+                }
+            }
+            AstNode::Expression(Expr::Negation { operand, .. }) => {
+                self.visit(self.ast.get_node_unchecked(*operand))?;
+                self.emit(Instr::Neg);
+                Ok(())
+            }
+            AstNode::Expression(Expr::Not { operand, .. }) => {
+                self.visit(self.ast.get_node_unchecked(*operand))?;
+                self.emit(Instr::Not);
+                Ok(())
+            }
+            AstNode::Expression(Expr::Assign { target, value, .. }) => {
+                let name = self.ast.get_string_unchecked(*target);
+                let slot = self
+                    .resolve(&name)
+                    .ok_or_else(|| format!("unbound name `{}`", name))?;
+                self.visit(self.ast.get_node_unchecked(*value))?;
+                self.emit(Instr::StoreLocal(slot));
+                // assignment is an expression, so leave the stored value on the stack
+                self.emit(Instr::LoadLocal(slot));
+                Ok(())
+            }
+            AstNode::Expression(Expr::Grouped {
+                expression_node_idx,
+                ..
+            }) => self.visit(self.ast.get_node_unchecked(*expression_node_idx)),
+            AstNode::Expression(Expr::Return {
+                expression_node_idx,
+                ..
+            }) => {
+                match expression_node_idx {
+                    Some(idx) => self.visit(self.ast.get_node_unchecked(*idx))?,
+                    None => {
+                        self.emit(Instr::PushUnit);
+                    }
+                }
+                self.emit(Instr::Return);
+                Ok(())
+            }
+            other => panic!("BUG: cannot compile node `{:?}`", other),
+        }
+    }
+}
+
+/// A small stack machine that executes a [`Program`].
+#[derive(Debug)]
+pub struct Vm {
+    program: Program,
+}
+
+impl Vm {
+    pub fn new(program: Program) -> Self {
+        Self { program }
+    }
+
+    pub fn run(&self) -> Result<Value, String> {
+        let mut stack: Vec<Value> = Vec::with_capacity(32);
+        let mut locals: Vec<Value> = vec![Value::Unit; self.program.num_locals];
+        let mut pc = 0;
+
+        let pop = |stack: &mut Vec<Value>| {
+            stack
+                .pop()
+                .unwrap_or_else(|| panic!("BUG: stack underflow"))
+        };
+
+        while pc < self.program.code.len() {
+            match &self.program.code[pc] {
+                Instr::PushI64(n) => stack.push(Value::Int(*n)),
+                Instr::PushStr(idx) => stack.push(Value::Str(self.program.strings[*idx].clone())),
+                Instr::PushBool(b) => stack.push(Value::Bool(*b)),
+                Instr::PushUnit => stack.push(Value::Unit),
+                Instr::Pop => {
+                    pop(&mut stack);
+                }
+                Instr::LoadLocal(slot) => stack.push(locals[*slot].clone()),
+                Instr::StoreLocal(slot) => locals[*slot] = pop(&mut stack),
+                Instr::BinOp(op) => {
+                    let rhs = pop(&mut stack);
+                    let lhs = pop(&mut stack);
+                    stack.push(eval_bin_op(op, lhs, rhs)?);
+                }
+                Instr::Neg => match pop(&mut stack) {
+                    Value::Int(n) => stack.push(Value::Int(
+                        n.checked_neg().ok_or_else(|| format!("negation of `{}` overflows", n))?,
+                    )),
+                    other => return Err(format!("cannot negate a {}", other_type_name(&other))),
+                },
+                Instr::Not => match pop(&mut stack) {
+                    Value::Bool(b) => stack.push(Value::Bool(!b)),
+                    other => {
+                        return Err(format!("cannot apply `!` to a {}", other_type_name(&other)))
+                    }
+                },
+                Instr::JumpIfFalse(addr) => match pop(&mut stack) {
+                    Value::Bool(false) => {
+                        pc = *addr;
+                        continue;
+                    }
+                    Value::Bool(true) => {}
+                    other => {
+                        return Err(format!(
+                            "condition must be a bool, but got a {}",
+                            other_type_name(&other)
+                        ))
+                    }
+                },
+                Instr::Jump(addr) => {
+                    pc = *addr;
+                    continue;
+                }
+                Instr::Return => return Ok(pop(&mut stack)),
+            }
+            pc += 1;
+        }
+
+        Ok(stack.pop().unwrap_or(Value::Unit))
+    }
+}
+
+fn other_type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Int(_) => "int",
+        Value::Bool(_) => "bool",
+        Value::Str(_) => "string",
+        Value::Unit => "unit",
+    }
+}
+
+fn eval_bin_op(op: &BinOp, lhs: Value, rhs: Value) -> Result<Value, String> {
+    let (lhs, rhs) = match (lhs, rhs) {
+        (Value::Int(l), Value::Int(r)) => (l, r),
+        (lhs, rhs) => {
+            return Err(format!(
+                "operator `{:?}` expects two ints, but got {} and {}",
+                op,
+                other_type_name(&lhs),
+                other_type_name(&rhs)
+            ))
+        }
+    };
+    let value = match op {
+        BinOp::Add => {
+            Value::Int(lhs.checked_add(rhs).ok_or_else(|| "`+` overflows".to_owned())?)
+        }
+        BinOp::Sub => {
+            Value::Int(lhs.checked_sub(rhs).ok_or_else(|| "`-` overflows".to_owned())?)
+        }
+        BinOp::Mul => {
+            Value::Int(lhs.checked_mul(rhs).ok_or_else(|| "`*` overflows".to_owned())?)
+        }
+        BinOp::Pow => Value::Int(
+            u32::try_from(rhs)
+                .ok()
+                .and_then(|exp| lhs.checked_pow(exp))
+                .ok_or_else(|| "`**` overflows".to_owned())?,
+        ),
+        BinOp::Div => {
+            Value::Int(lhs.checked_div(rhs).ok_or_else(|| "`/` overflows".to_owned())?)
+        }
+        BinOp::Rem => {
+            Value::Int(lhs.checked_rem(rhs).ok_or_else(|| "`%` overflows".to_owned())?)
+        }
+        BinOp::Eq => Value::Bool(lhs == rhs),
+        BinOp::Ne => Value::Bool(lhs != rhs),
+        BinOp::Lt => Value::Bool(lhs < rhs),
+        BinOp::Le => Value::Bool(lhs <= rhs),
+        BinOp::Gt => Value::Bool(lhs > rhs),
+        BinOp::Ge => Value::Bool(lhs >= rhs),
+    };
+    Ok(value)
+}
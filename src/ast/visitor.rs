@@ -1,5 +1,6 @@
-use super::{Anno, Ast, AstError, AstNode, Expr, Stat};
+use super::{Anno, Ast, AstError, AstNode, AstNodeIdx, Expr, Pat, Stat};
 use crate::lexer;
+use std::collections::HashMap;
 
 pub trait AstNodeVisitor<R> {
     fn visit(&mut self, node: &AstNode) -> R;
@@ -98,7 +99,9 @@ impl<'cu, E: AstError> AstPrinter<'cu, E> {
                     }
                 )
             }
-            AstNode::Expression(Expr::I64(token_idx)) => self.ast.get_string_unchecked(*token_idx),
+            AstNode::Expression(Expr::Int { token_idx, .. }) => {
+                self.ast.get_string_unchecked(*token_idx)
+            }
             AstNode::Expression(Expr::Identifier(token_idx)) => {
                 self.ast.get_string_unchecked(*token_idx)
             }
@@ -122,6 +125,21 @@ impl<'cu, E: AstError> AstPrinter<'cu, E> {
                     ),
                 }
             }
+            AstNode::Expression(Expr::Not { operator, operand }) => {
+                format!(
+                    "{}{}",
+                    self.ast.get_string_unchecked(*operator),
+                    self.ast.get_string_unchecked(*operand)
+                )
+            }
+            AstNode::Expression(Expr::Assign { target, eq, value }) => {
+                format!(
+                    "{} {} {}",
+                    self.ast.get_string_unchecked(*target),
+                    self.ast.get_string_unchecked(*eq),
+                    self.ast.get_string_unchecked(*value)
+                )
+            }
             AstNode::Expression(Expr::Grouped {
                 lparen,
                 expression_node_idx,
@@ -134,13 +152,40 @@ impl<'cu, E: AstError> AstPrinter<'cu, E> {
                     self.ast.get_string_unchecked(*rparen)
                 )
             }
+            AstNode::Expression(Expr::Call {
+                callee,
+                args,
+                ..
+            }) => {
+                format!(
+                    "{}({})",
+                    self.ast.get_string_unchecked(*callee),
+                    args.iter()
+                        .map(|idx| self.ast.get_string_unchecked(*idx))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            }
+            AstNode::Expression(Expr::Index { base, index, .. }) => {
+                format!(
+                    "{}[{}]",
+                    self.ast.get_string_unchecked(*base),
+                    self.ast.get_string_unchecked(*index)
+                )
+            }
             AstNode::Expression(Expr::Block {
                 lcurlybracket,
                 statements_node_indices,
+                tail_expression_node_idx,
                 rcurlybracket,
             }) => {
+                let tail = tail_expression_node_idx
+                    .map(|idx| {
+                        self.print_with_indent(self.ast.get_node_unchecked(idx), indent + 1)
+                    })
+                    .unwrap_or_default();
                 format!(
-                    "{}\n{}{}",
+                    "{}\n{}{}{}",
                     self.ast.get_string_unchecked(*lcurlybracket),
                     statements_node_indices
                         .iter()
@@ -148,6 +193,7 @@ impl<'cu, E: AstError> AstPrinter<'cu, E> {
                             .print_with_indent(self.ast.get_node_unchecked(*idx), indent + 1))
                         .collect::<Vec<_>>()
                         .join(""),
+                    tail,
                     self.ast.get_string_unchecked(*rcurlybracket)
                 )
             }
@@ -163,150 +209,700 @@ impl<'cu, E: AstError> AstPrinter<'cu, E> {
                         .unwrap_or_else(|| "".to_owned())
                 )
             }
+            AstNode::Expression(Expr::While {
+                while_kw,
+                condition_node_idx,
+                body_block_node_idx,
+            }) => {
+                format!(
+                    "{} {} {}",
+                    self.ast.get_string_unchecked(*while_kw),
+                    self.ast.get_string_unchecked(*condition_node_idx),
+                    self.ast.get_string_unchecked(*body_block_node_idx)
+                )
+            }
+            AstNode::Expression(Expr::Loop {
+                loop_kw,
+                body_block_node_idx,
+            }) => {
+                format!(
+                    "{} {}",
+                    self.ast.get_string_unchecked(*loop_kw),
+                    self.ast.get_string_unchecked(*body_block_node_idx)
+                )
+            }
+            AstNode::Expression(Expr::Break {
+                break_kw,
+                expression_node_idx,
+            }) => {
+                format!(
+                    "{} {}",
+                    self.ast.get_string_unchecked(*break_kw),
+                    expression_node_idx
+                        .map(|idx| self.ast.get_string_unchecked(idx))
+                        .unwrap_or_else(|| "".to_owned())
+                )
+            }
+            AstNode::Expression(Expr::Continue { continue_kw }) => {
+                self.ast.get_string_unchecked(*continue_kw)
+            }
+            AstNode::Expression(Expr::Match {
+                match_kw,
+                scrutinee_node_idx,
+                arms,
+            }) => {
+                format!(
+                    "{} {} {{ {} }}",
+                    self.ast.get_string_unchecked(*match_kw),
+                    self.ast.get_string_unchecked(*scrutinee_node_idx),
+                    arms.iter()
+                        .map(|arm| format!(
+                            "{} => {}",
+                            self.ast.get_string_unchecked(arm.pattern_node_idx),
+                            self.ast.get_string_unchecked(arm.body_node_idx)
+                        ))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            }
+            AstNode::Expression(Expr::Let {
+                pattern_node_idx,
+                eq_token,
+                value_node_idx,
+            }) => {
+                format!(
+                    "let {} {} {}",
+                    self.ast.get_string_unchecked(*pattern_node_idx),
+                    self.ast.get_string_unchecked(*eq_token),
+                    self.ast.get_string_unchecked(*value_node_idx)
+                )
+            }
+            AstNode::Expression(Expr::Error { span_token_idx }) => {
+                self.ast.get_string_unchecked(*span_token_idx)
+            }
             AstNode::Annotation(Anno::Type { token_idx }) => {
                 self.ast.get_string_unchecked(*token_idx)
             }
+            AstNode::Annotation(Anno::Generic { head, args }) => {
+                format!(
+                    "{}<{}>",
+                    self.ast.get_string_unchecked(*head),
+                    args.iter()
+                        .map(|idx| self.ast.get_string_unchecked(*idx))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            }
+            AstNode::Annotation(Anno::Tuple { elems }) => {
+                format!(
+                    "({})",
+                    elems
+                        .iter()
+                        .map(|idx| self.ast.get_string_unchecked(*idx))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            }
+            AstNode::Annotation(Anno::Func { params, ret, .. }) => {
+                format!(
+                    "fn({}) -> {}",
+                    params
+                        .iter()
+                        .map(|idx| self.ast.get_string_unchecked(*idx))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    self.ast.get_string_unchecked(*ret)
+                )
+            }
+            AstNode::Pattern(Pat::Wildcard { token_idx })
+            | AstNode::Pattern(Pat::Binding { token_idx })
+            | AstNode::Pattern(Pat::Int { token_idx }) => {
+                self.ast.get_string_unchecked(*token_idx)
+            }
+            AstNode::Pattern(Pat::StringLiteral { content, .. }) => content.clone(),
+            AstNode::Pattern(Pat::Tuple { elems, .. }) => {
+                format!(
+                    "({})",
+                    elems
+                        .iter()
+                        .map(|idx| self.ast.get_string_unchecked(*idx))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            }
         }
     }
 }
 
+/// A read-only visitor that renders the tree as parenthesized S-expressions.
+///
+/// Unlike [`AstPrinter`], which reproduces surface syntax and is therefore lossy about structure,
+/// this dump exposes operator nesting, which branch an `else` took, and implicit grouping. The
+/// output is whitespace- and precedence-independent, making it ideal for golden/snapshot tests of
+/// the parser.
 #[derive(Debug)]
-pub struct AstEvaluator<'cu, E: AstError> {
+pub struct AstSexpDumper<'cu, E: AstError> {
     ast: &'cu Ast<'cu, E>,
 }
 
-impl<'cu, E: AstError> AstEvaluator<'cu, E> {
+impl<'cu, E: AstError> AstSexpDumper<'cu, E> {
     pub fn new(ast: &'cu Ast<'cu, E>) -> Self {
         Self { ast }
     }
+    fn dump(&mut self, node_idx: AstNodeIdx) -> String {
+        self.visit(self.ast.get_node_unchecked(node_idx))
+    }
 }
 
-impl<'cu, E: AstError> AstNodeVisitor<Result<Option<i64>, String>> for AstEvaluator<'cu, E> {
-    fn visit(&mut self, node: &AstNode) -> Result<Option<i64>, String> {
+impl<'cu, E: AstError> AstNodeVisitor<String> for AstSexpDumper<'cu, E> {
+    fn visit(&mut self, node: &AstNode) -> String {
         match node {
             AstNode::Module {
                 statements_node_indices,
             } => {
-                // TODO: should be (), just returning the value of the first statement for now
-                if statements_node_indices.is_empty() {
-                    return Ok(None);
+                let children: Vec<String> =
+                    statements_node_indices.iter().map(|idx| self.dump(*idx)).collect();
+                format!("(module {})", children.join(" "))
+            }
+            AstNode::Statement(Stat::Definition {
+                lhs_expression_node_idx,
+                type_node_idx,
+                rhs_expression_node_idx,
+                ..
+            }) => {
+                let type_ = type_node_idx
+                    .map(|idx| format!(" {}", self.dump(idx)))
+                    .unwrap_or_default();
+                format!(
+                    "(def {}{} {})",
+                    self.dump(*lhs_expression_node_idx),
+                    type_,
+                    self.dump(*rhs_expression_node_idx)
+                )
+            }
+            AstNode::Statement(Stat::Expression(expression_node_idx)) => {
+                self.dump(*expression_node_idx)
+            }
+            AstNode::Expression(Expr::If {
+                condition_node_idx,
+                then_block_node_idx,
+                else_block_node_idx,
+                if_node_idx,
+                ..
+            }) => {
+                let else_ = else_block_node_idx
+                    .or(*if_node_idx)
+                    .map(|idx| format!(" {}", self.dump(idx)))
+                    .unwrap_or_default();
+                format!(
+                    "(if {} {}{})",
+                    self.dump(*condition_node_idx),
+                    self.dump(*then_block_node_idx),
+                    else_
+                )
+            }
+            AstNode::Expression(Expr::Int { token_idx, .. }) => {
+                format!("(i64 {})", self.ast.get_string_unchecked(*token_idx))
+            }
+            AstNode::Expression(Expr::Identifier(token_idx)) => {
+                format!("(id {})", self.ast.get_string_unchecked(*token_idx))
+            }
+            AstNode::Expression(Expr::StringLiteral { content, .. }) => {
+                format!("(str {})", content)
+            }
+            AstNode::Expression(Expr::ArithmeticOrLogical { operator, lhs, rhs }) => {
+                format!(
+                    "({} {} {})",
+                    self.ast.get_string_unchecked(*operator),
+                    self.dump(*lhs),
+                    self.dump(*rhs)
+                )
+            }
+            AstNode::Expression(Expr::Negation { operand, .. }) => {
+                format!("(neg {})", self.dump(*operand))
+            }
+            AstNode::Expression(Expr::Not { operand, .. }) => {
+                format!("(not {})", self.dump(*operand))
+            }
+            AstNode::Expression(Expr::Assign { target, value, .. }) => {
+                format!("(= {} {})", self.dump(*target), self.dump(*value))
+            }
+            AstNode::Expression(Expr::Call { callee, args, .. }) => {
+                let mut parts = vec![format!("call {}", self.dump(*callee))];
+                parts.extend(args.iter().map(|idx| self.dump(*idx)));
+                format!("({})", parts.join(" "))
+            }
+            AstNode::Expression(Expr::Index { base, index, .. }) => {
+                format!("(index {} {})", self.dump(*base), self.dump(*index))
+            }
+            AstNode::Expression(Expr::Grouped {
+                expression_node_idx,
+                ..
+            }) => format!("(group {})", self.dump(*expression_node_idx)),
+            AstNode::Expression(Expr::Block {
+                statements_node_indices,
+                tail_expression_node_idx,
+                ..
+            }) => {
+                let mut children: Vec<String> =
+                    statements_node_indices.iter().map(|idx| self.dump(*idx)).collect();
+                if let Some(idx) = tail_expression_node_idx {
+                    children.push(format!("(tail {})", self.dump(*idx)));
                 }
-                self.visit(self.ast.get_node_unchecked(statements_node_indices[0]))
+                format!("(block {})", children.join(" "))
+            }
+            AstNode::Expression(Expr::Return {
+                expression_node_idx,
+                ..
+            }) => {
+                let value = expression_node_idx
+                    .map(|idx| format!(" {}", self.dump(idx)))
+                    .unwrap_or_default();
+                format!("(return{})", value)
+            }
+            AstNode::Expression(Expr::While {
+                condition_node_idx,
+                body_block_node_idx,
+                ..
+            }) => {
+                format!(
+                    "(while {} {})",
+                    self.dump(*condition_node_idx),
+                    self.dump(*body_block_node_idx)
+                )
+            }
+            AstNode::Expression(Expr::Loop {
+                body_block_node_idx,
+                ..
+            }) => {
+                format!("(loop {})", self.dump(*body_block_node_idx))
+            }
+            AstNode::Expression(Expr::Break {
+                expression_node_idx,
+                ..
+            }) => {
+                let value = expression_node_idx
+                    .map(|idx| format!(" {}", self.dump(idx)))
+                    .unwrap_or_default();
+                format!("(break{})", value)
+            }
+            AstNode::Expression(Expr::Continue { .. }) => "(continue)".to_owned(),
+            AstNode::Expression(Expr::Match {
+                scrutinee_node_idx,
+                arms,
+                ..
+            }) => {
+                let mut parts = vec![format!("match {}", self.dump(*scrutinee_node_idx))];
+                parts.extend(arms.iter().map(|arm| {
+                    format!(
+                        "(arm {} {})",
+                        self.dump(arm.pattern_node_idx),
+                        self.dump(arm.body_node_idx)
+                    )
+                }));
+                format!("({})", parts.join(" "))
+            }
+            AstNode::Expression(Expr::Let {
+                pattern_node_idx,
+                value_node_idx,
+                ..
+            }) => {
+                format!(
+                    "(let {} {})",
+                    self.dump(*pattern_node_idx),
+                    self.dump(*value_node_idx)
+                )
+            }
+            AstNode::Expression(Expr::Error { .. }) => "(error)".to_owned(),
+            AstNode::Annotation(Anno::Type { token_idx }) => {
+                format!("(type {})", self.ast.get_string_unchecked(*token_idx))
+            }
+            AstNode::Annotation(Anno::Generic { head, args }) => {
+                let mut parts = vec![format!("generic {}", self.ast.get_string_unchecked(*head))];
+                parts.extend(args.iter().map(|idx| self.dump(*idx)));
+                format!("({})", parts.join(" "))
+            }
+            AstNode::Annotation(Anno::Tuple { elems }) => {
+                let children: Vec<String> = elems.iter().map(|idx| self.dump(*idx)).collect();
+                format!("(tuple {})", children.join(" "))
+            }
+            AstNode::Annotation(Anno::Func { params, ret, .. }) => {
+                let children: Vec<String> = params.iter().map(|idx| self.dump(*idx)).collect();
+                format!("(fn ({}) {})", children.join(" "), self.dump(*ret))
+            }
+            AstNode::Pattern(Pat::Wildcard { .. }) => "(pat _)".to_owned(),
+            AstNode::Pattern(Pat::Binding { token_idx }) => {
+                format!("(bind {})", self.ast.get_string_unchecked(*token_idx))
+            }
+            AstNode::Pattern(Pat::Int { token_idx }) => {
+                format!("(i64 {})", self.ast.get_string_unchecked(*token_idx))
+            }
+            AstNode::Pattern(Pat::StringLiteral { content, .. }) => {
+                format!("(str {})", content)
+            }
+            AstNode::Pattern(Pat::Tuple { elems, .. }) => {
+                let children: Vec<String> = elems.iter().map(|idx| self.dump(*idx)).collect();
+                format!("(tuple-pat {})", children.join(" "))
+            }
+        }
+    }
+}
+
+/// A runtime value produced by [`AstEvaluator`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Int(i64),
+    Bool(bool),
+    Str(String),
+    Unit,
+}
+
+impl Value {
+    fn type_name(&self) -> &'static str {
+        match self {
+            Value::Int(_) => "int",
+            Value::Bool(_) => "bool",
+            Value::Str(_) => "string",
+            Value::Unit => "unit",
+        }
+    }
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Value::Int(n) => write!(f, "{}", n),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Str(s) => write!(f, "{}", s),
+            Value::Unit => write!(f, "()"),
+        }
+    }
+}
+
+/// An error raised while evaluating an [`Ast`].
+///
+/// Each variant carries the token that anchors the error so it can be rendered with a source
+/// caret through [`DiagCtx`](crate::lexer::DiagCtx), the same way parse errors are.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EvalError {
+    UnboundName {
+        token_idx: lexer::TokenIdx,
+        name: String,
+    },
+    TypeError {
+        token_idx: lexer::TokenIdx,
+        msg: String,
+    },
+    Overflow {
+        token_idx: lexer::TokenIdx,
+        msg: String,
+    },
+}
+
+impl EvalError {
+    fn message(&self) -> String {
+        match self {
+            EvalError::UnboundName { name, .. } => format!("unbound name `{}`", name),
+            EvalError::TypeError { msg, .. } => format!("type error: {}", msg),
+            EvalError::Overflow { msg, .. } => msg.clone(),
+        }
+    }
+
+    fn token_idx(&self) -> lexer::TokenIdx {
+        match self {
+            EvalError::UnboundName { token_idx, .. }
+            | EvalError::TypeError { token_idx, .. }
+            | EvalError::Overflow { token_idx, .. } => *token_idx,
+        }
+    }
+
+    /// Renders the error with a source caret drawn by the compilation unit's [`DiagCtx`].
+    pub fn get_string<E: AstError>(&self, ast: &Ast<E>) -> String {
+        use colored::Colorize;
+        format!(
+            "{filename}: {cate}: {msg}\n{diag}",
+            filename = ast.get_input_origin().bold(),
+            cate = "error".red().bold(),
+            msg = self.message().red().bold(),
+            diag = ast.get_diag_with_error_token(self.token_idx(), lexer::SpanLabels::none()),
+        )
+    }
+}
+
+impl std::fmt::Display for EvalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.message())
+    }
+}
+
+#[derive(Debug)]
+pub struct AstEvaluator<'cu, E: AstError> {
+    ast: &'cu Ast<'cu, E>,
+    // innermost-to-outermost scopes are searched back-to-front; the first frame is the module scope
+    scopes: Vec<HashMap<String, Value>>,
+    // set once a `return` is seen so enclosing blocks short-circuit the rest of their statements
+    returning: bool,
+}
+
+impl<'cu, E: AstError> AstEvaluator<'cu, E> {
+    pub fn new(ast: &'cu Ast<'cu, E>) -> Self {
+        Self {
+            ast,
+            scopes: vec![HashMap::new()],
+            returning: false,
+        }
+    }
+
+    // evaluates statements in order in the current frame, the value being that of the last
+    // statement (or `Unit` for an empty body), short-circuiting as soon as a `return` sets the
+    // `returning` flag
+    fn eval_statements(
+        &mut self,
+        statements_node_indices: &[AstNodeIdx],
+    ) -> Result<Value, EvalError> {
+        let mut value = Value::Unit;
+        for idx in statements_node_indices {
+            value = self.visit(self.ast.get_node_unchecked(*idx))?;
+            if self.returning {
+                break;
             }
+        }
+        Ok(value)
+    }
+
+    fn lookup(&self, name: &str) -> Option<Value> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name).cloned())
+    }
+
+    // finds a representative token to anchor a diagnostic at the given node
+    fn token_of(&self, node_idx: AstNodeIdx) -> lexer::TokenIdx {
+        match self.ast.get_node_unchecked(node_idx) {
+            AstNode::Expression(Expr::Int { token_idx, .. })
+            | AstNode::Expression(Expr::Identifier(token_idx))
+            | AstNode::Expression(Expr::StringLiteral { token_idx, .. }) => *token_idx,
+            AstNode::Expression(Expr::ArithmeticOrLogical { operator, .. })
+            | AstNode::Expression(Expr::Negation { operator, .. })
+            | AstNode::Expression(Expr::Not { operator, .. }) => *operator,
+            AstNode::Expression(Expr::Assign { eq, .. }) => *eq,
+            AstNode::Expression(Expr::Grouped { lparen, .. }) => *lparen,
+            AstNode::Expression(Expr::Block { lcurlybracket, .. }) => *lcurlybracket,
+            AstNode::Expression(Expr::If { if_kw, .. }) => *if_kw,
+            AstNode::Expression(Expr::Return { return_kw, .. }) => *return_kw,
+            AstNode::Expression(Expr::While { while_kw, .. }) => *while_kw,
+            AstNode::Expression(Expr::Loop { loop_kw, .. }) => *loop_kw,
+            AstNode::Expression(Expr::Break { break_kw, .. }) => *break_kw,
+            AstNode::Expression(Expr::Continue { continue_kw, .. }) => *continue_kw,
+            AstNode::Expression(Expr::Error { span_token_idx }) => *span_token_idx,
+            other => panic!("BUG: cannot anchor a diagnostic at `{:?}`", other),
+        }
+    }
+
+    fn type_error(&self, node_idx: AstNodeIdx, expected: &str, got: &Value) -> EvalError {
+        EvalError::TypeError {
+            token_idx: self.token_of(node_idx),
+            msg: format!(
+                "expected {}, but `{}` is a {}",
+                expected,
+                self.ast.get_string_unchecked(node_idx),
+                got.type_name()
+            ),
+        }
+    }
+
+    fn expect_int(&mut self, node_idx: AstNodeIdx) -> Result<i64, EvalError> {
+        match self.visit(self.ast.get_node_unchecked(node_idx))? {
+            Value::Int(n) => Ok(n),
+            other => Err(self.type_error(node_idx, "an int", &other)),
+        }
+    }
+
+    fn expect_bool(&mut self, node_idx: AstNodeIdx) -> Result<bool, EvalError> {
+        match self.visit(self.ast.get_node_unchecked(node_idx))? {
+            Value::Bool(b) => Ok(b),
+            other => Err(self.type_error(node_idx, "a bool", &other)),
+        }
+    }
+}
+
+impl<'cu, E: AstError> AstNodeVisitor<Result<Value, EvalError>> for AstEvaluator<'cu, E> {
+    fn visit(&mut self, node: &AstNode) -> Result<Value, EvalError> {
+        match node {
+            AstNode::Module {
+                statements_node_indices,
+            } => self.eval_statements(statements_node_indices),
             AstNode::Expression(Expr::If {
                 condition_node_idx,
                 then_block_node_idx: then_node_idx,
                 else_block_node_idx: else_node_idx,
+                if_node_idx,
                 ..
             }) => {
-                let condition_value = self
-                    .visit(self.ast.get_node_unchecked(*condition_node_idx))?
-                    .unwrap_or_else(|| {
-                        panic!(
-                            "BUG: expected condition to be Some, but got None: {}",
-                            self.ast.get_string_unchecked(*condition_node_idx)
-                        )
-                    });
-                if condition_value != 0 {
+                let condition_value =
+                    match self.visit(self.ast.get_node_unchecked(*condition_node_idx))? {
+                        Value::Bool(b) => b,
+                        other => {
+                            return Err(self.type_error(
+                                *condition_node_idx,
+                                "an `if` condition of type bool",
+                                &other,
+                            ))
+                        }
+                    };
+                if condition_value {
                     self.visit(self.ast.get_node_unchecked(*then_node_idx))
                 } else if let Some(else_node_idx) = else_node_idx {
                     self.visit(self.ast.get_node_unchecked(*else_node_idx))
+                } else if let Some(if_node_idx) = if_node_idx {
+                    self.visit(self.ast.get_node_unchecked(*if_node_idx))
                 } else {
-                    Ok(None)
+                    Ok(Value::Unit)
                 }
             }
             AstNode::Expression(Expr::Block {
                 statements_node_indices,
+                tail_expression_node_idx,
                 ..
             }) => {
-                unimplemented!("{:?}", statements_node_indices);
+                self.scopes.push(HashMap::new());
+                let value = self.eval_statements(statements_node_indices).and_then(|stmt_value| {
+                    match tail_expression_node_idx {
+                        // the block yields its trailing expression, unless a `return` already fired
+                        Some(idx) if !self.returning => {
+                            self.visit(self.ast.get_node_unchecked(*idx))
+                        }
+                        _ => Ok(stmt_value),
+                    }
+                });
+                self.scopes.pop();
+                value
             }
             AstNode::Statement(Stat::Expression(expression_node_idx)) => {
                 // TODO: should be (), just returning the value for expression for now
                 self.visit(self.ast.get_node_unchecked(*expression_node_idx))
             }
-            AstNode::Statement(Stat::Definition { .. }) => {
-                panic!("doesn't support eval a definition")
-            }
-            AstNode::Expression(Expr::I64(token_idx)) => {
-                let token = &self.ast.get_token_unchecked(*token_idx);
-                match token.get_kind() {
-                    lexer::TokenKind::I64 => self
-                        .ast
-                        .get_string_unchecked(*token_idx)
-                        .parse()
-                        .map_err(|e| {
-                            format!(
-                                "BUG: failed to parse i64 token `{}`: {}",
-                                self.ast.get_string_unchecked(*token_idx),
-                                e
-                            )
-                        })
-                        .map(Some),
-                    _ => panic!("BUG: expected i64 token, but got `{:?}`", token.get_kind()),
-                }
+            AstNode::Statement(Stat::Definition {
+                lhs_expression_node_idx,
+                rhs_expression_node_idx,
+                ..
+            }) => {
+                let value = self.visit(self.ast.get_node_unchecked(*rhs_expression_node_idx))?;
+                let name = self.ast.get_string_unchecked(*lhs_expression_node_idx);
+                self.scopes
+                    .last_mut()
+                    .expect("BUG: evaluator must always have at least the module scope")
+                    .insert(name, value);
+                Ok(Value::Unit)
             }
-            AstNode::Expression(Expr::StringLiteral { .. }) => {
-                panic!("doesn't support eval a string literal")
+            AstNode::Expression(Expr::Int {
+                token_idx,
+                bits,
+                signed,
+            }) => super::parse_int_literal(self.ast.get_token_str(*token_idx), *bits, *signed)
+                .map(Value::Int)
+                .map_err(|msg| EvalError::Overflow {
+                    token_idx: *token_idx,
+                    msg,
+                }),
+            AstNode::Expression(Expr::StringLiteral { content, .. }) => {
+                Ok(Value::Str(content.clone()))
             }
-            AstNode::Expression(Expr::Identifier(_)) => {
-                panic!("BUG: doesn't support eval an identifier")
+            AstNode::Expression(Expr::Identifier(token_idx)) => {
+                let name = self.ast.get_string_unchecked(*token_idx);
+                self.lookup(&name).ok_or(EvalError::UnboundName {
+                    token_idx: *token_idx,
+                    name,
+                })
             }
             AstNode::Expression(Expr::ArithmeticOrLogical { operator, lhs, rhs }) => {
-                let lhs_str = self.ast.get_string_unchecked(*lhs);
-                let rhs_str = self.ast.get_string_unchecked(*rhs);
-                let lhs_value = self
-                    .visit(self.ast.get_node_unchecked(*lhs))?
-                    .unwrap_or_else(|| {
-                        panic!(
-                            "BUG: expected lhs to be Some, but got None: {}",
-                            self.ast.get_string_unchecked(*lhs)
-                        )
-                    });
-                let rhs_value = self
-                    .visit(self.ast.get_node_unchecked(*rhs))?
-                    .unwrap_or_else(|| {
-                        panic!(
-                            "BUG: expected rhs to be Some, but got None: {}",
-                            self.ast.get_string_unchecked(*rhs)
-                        )
-                    });
-                match self.ast.get_token_unchecked(*operator).get_kind() {
-                    lexer::TokenKind::Plus => lhs_value
-                        .checked_add(rhs_value)
-                        .ok_or_else(|| format!("`{}` + `{}` overflows", lhs_str, rhs_str)),
-                    lexer::TokenKind::Minus => lhs_value
-                        .checked_sub(rhs_value)
-                        .ok_or_else(|| format!("`{}` - `{}` overflows", lhs_str, rhs_str)),
-                    lexer::TokenKind::Star => lhs_value
-                        .checked_mul(rhs_value)
-                        .ok_or_else(|| format!("`{}` * `{}` overflows", lhs_str, rhs_str)),
-                    lexer::TokenKind::Slash => lhs_value
-                        .checked_div(rhs_value)
-                        .ok_or_else(|| format!("`{}` / `{}` overflows", lhs_str, rhs_str)),
-                    lexer::TokenKind::Percent => lhs_value
-                        .checked_rem(rhs_value)
-                        .ok_or_else(|| format!("`{}` % `{}` overflows", lhs_str, rhs_str)),
+                let op_kind = self.ast.get_token_unchecked(*operator).get_kind().clone();
+                match op_kind {
+                    // short-circuiting logical operators: only visit `rhs` when `lhs` hasn't
+                    // already decided the outcome
+                    lexer::TokenKind::AmpAmp => {
+                        if !self.expect_bool(*lhs)? {
+                            return Ok(Value::Bool(false));
+                        }
+                        self.expect_bool(*rhs).map(Value::Bool)
+                    }
+                    lexer::TokenKind::PipePipe => {
+                        if self.expect_bool(*lhs)? {
+                            return Ok(Value::Bool(true));
+                        }
+                        self.expect_bool(*rhs).map(Value::Bool)
+                    }
+                    // comparisons produce a bool from two ints
+                    lexer::TokenKind::EqEq
+                    | lexer::TokenKind::Ne
+                    | lexer::TokenKind::Lt
+                    | lexer::TokenKind::Le
+                    | lexer::TokenKind::Gt
+                    | lexer::TokenKind::Ge => {
+                        let lhs_value = self.expect_int(*lhs)?;
+                        let rhs_value = self.expect_int(*rhs)?;
+                        let result = match op_kind {
+                            lexer::TokenKind::EqEq => lhs_value == rhs_value,
+                            lexer::TokenKind::Ne => lhs_value != rhs_value,
+                            lexer::TokenKind::Lt => lhs_value < rhs_value,
+                            lexer::TokenKind::Le => lhs_value <= rhs_value,
+                            lexer::TokenKind::Gt => lhs_value > rhs_value,
+                            lexer::TokenKind::Ge => lhs_value >= rhs_value,
+                            _ => unreachable!(),
+                        };
+                        Ok(Value::Bool(result))
+                    }
+                    // arithmetic
+                    lexer::TokenKind::Plus
+                    | lexer::TokenKind::Minus
+                    | lexer::TokenKind::Star
+                    | lexer::TokenKind::StarStar
+                    | lexer::TokenKind::Slash
+                    | lexer::TokenKind::Percent => {
+                        let lhs_str = self.ast.get_string_unchecked(*lhs);
+                        let rhs_str = self.ast.get_string_unchecked(*rhs);
+                        let lhs_value = self.expect_int(*lhs)?;
+                        let rhs_value = self.expect_int(*rhs)?;
+                        let (result, sign) = match op_kind {
+                            lexer::TokenKind::Plus => (lhs_value.checked_add(rhs_value), "+"),
+                            lexer::TokenKind::Minus => (lhs_value.checked_sub(rhs_value), "-"),
+                            lexer::TokenKind::Star => (lhs_value.checked_mul(rhs_value), "*"),
+                            lexer::TokenKind::StarStar => (
+                                u32::try_from(rhs_value)
+                                    .ok()
+                                    .and_then(|exp| lhs_value.checked_pow(exp)),
+                                "**",
+                            ),
+                            lexer::TokenKind::Slash => (lhs_value.checked_div(rhs_value), "/"),
+                            lexer::TokenKind::Percent => (lhs_value.checked_rem(rhs_value), "%"),
+                            _ => unreachable!(),
+                        };
+                        result.map(Value::Int).ok_or_else(|| EvalError::Overflow {
+                            token_idx: *operator,
+                            msg: format!("`{}` {} `{}` overflows", lhs_str, sign, rhs_str),
+                        })
+                    }
                     _ => panic!(
                         "BUG: unsupported binary operator `{}`",
                         self.ast.get_string_unchecked(*operator)
                     ),
                 }
-                .map(Some)
             }
             AstNode::Expression(Expr::Negation { operator, operand }) => {
                 match self.ast.get_token_unchecked(*operator).get_kind() {
                     lexer::TokenKind::Minus => {
-                        format!("-{}", self.ast.get_string_unchecked(*operand))
-                            .parse()
-                            .map_err(|e| {
-                                format!(
-                                    "BUG: failed to parse i64 token `{}`: {}",
-                                    self.ast.get_string_unchecked(*operand),
-                                    e
-                                )
+                        let operand_value = self.expect_int(*operand)?;
+                        operand_value
+                            .checked_neg()
+                            .ok_or_else(|| EvalError::Overflow {
+                                token_idx: *operator,
+                                msg: format!(
+                                    "negation of `{}` overflows",
+                                    self.ast.get_string_unchecked(*operand)
+                                ),
                             })
-                            .map(Some)
+                            .map(Value::Int)
                     }
                     _ => panic!(
                         "BUG: unsupported unary operator `{}`",
@@ -314,15 +910,77 @@ impl<'cu, E: AstError> AstNodeVisitor<Result<Option<i64>, String>> for AstEvalua
                     ),
                 }
             }
+            AstNode::Expression(Expr::Not { operand, .. }) => {
+                let operand_value = self.expect_bool(*operand)?;
+                Ok(Value::Bool(!operand_value))
+            }
+            AstNode::Expression(Expr::Assign { target, eq, value }) => {
+                let AstNode::Expression(Expr::Identifier(name_token_idx)) =
+                    self.ast.get_node_unchecked(*target)
+                else {
+                    return Err(EvalError::TypeError {
+                        token_idx: *eq,
+                        msg: format!(
+                            "cannot assign to `{}`",
+                            self.ast.get_string_unchecked(*target)
+                        ),
+                    });
+                };
+                let name_token_idx = *name_token_idx;
+                let name = self.ast.get_string_unchecked(name_token_idx);
+                let new_value = self.visit(self.ast.get_node_unchecked(*value))?;
+                match self
+                    .scopes
+                    .iter_mut()
+                    .rev()
+                    .find_map(|scope| scope.get_mut(&name))
+                {
+                    Some(slot) => {
+                        *slot = new_value.clone();
+                        Ok(new_value)
+                    }
+                    None => Err(EvalError::UnboundName {
+                        token_idx: name_token_idx,
+                        name,
+                    }),
+                }
+            }
             AstNode::Expression(Expr::Grouped {
                 expression_node_idx,
                 ..
             }) => self.visit(self.ast.get_node_unchecked(*expression_node_idx)),
+            AstNode::Expression(Expr::Call { .. } | Expr::Index { .. }) => {
+                panic!("BUG: doesn't support eval a call or index expression")
+            }
+            AstNode::Expression(
+                Expr::While { .. } | Expr::Loop { .. } | Expr::Break { .. } | Expr::Continue { .. },
+            ) => {
+                panic!("BUG: doesn't support eval a loop, break, or continue expression")
+            }
+            AstNode::Expression(Expr::Match { .. }) => {
+                panic!("BUG: doesn't support eval a match expression")
+            }
+            AstNode::Expression(Expr::Let { .. }) => {
+                panic!("BUG: doesn't support eval a let expression")
+            }
+            AstNode::Expression(Expr::Error { .. }) => {
+                panic!("BUG: doesn't support eval an error expression")
+            }
+            AstNode::Pattern(_) => {
+                panic!("BUG: doesn't support eval a pattern")
+            }
             AstNode::Expression(Expr::Return {
                 expression_node_idx,
                 ..
-            }) => self.visit(self.ast.get_node_unchecked(expression_node_idx.unwrap())),
-            AstNode::Annotation(Anno::Type { .. }) => {
+            }) => {
+                let value = match expression_node_idx {
+                    Some(idx) => self.visit(self.ast.get_node_unchecked(*idx))?,
+                    None => Value::Unit,
+                };
+                self.returning = true;
+                Ok(value)
+            }
+            AstNode::Annotation(_) => {
                 panic!("BUG: doesn't support eval an annotation")
             }
         }
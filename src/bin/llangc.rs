@@ -19,25 +19,55 @@ fn print_usage() {
     eprintln!("Usage: {} [OPTIONS] <INPUT>", PROG_NAME);
 }
 
+// how diagnostics are rendered for the user
+enum ErrorFormat {
+    // caret diagnostics with colour, the default for interactive use
+    Human,
+    // newline-delimited JSON records for tooling
+    Json,
+}
+
 fn main() {
     let args = std::env::args().collect::<Vec<_>>();
-    if args.len() < 2 {
+
+    let mut error_format = ErrorFormat::Human;
+    let mut filenames = Vec::new();
+    for arg in &args[1..] {
+        match arg.as_str() {
+            "--error-format=human" => error_format = ErrorFormat::Human,
+            "--error-format=json" => error_format = ErrorFormat::Json,
+            other => filenames.push(other),
+        }
+    }
+
+    if filenames.is_empty() {
         print_fatal_error("no input files");
         print_usage();
         return;
     }
 
-    for filename in &args[1..] {
-        match lexer::CompilationUnit::from_file(filename) {
-            Ok(cu) => {
-                let ast = parser::parse(&cu);
-                if let Some(diag) = ast.get_diagnostics() {
-                    eprintln!("{}", diag);
-                }
-            }
+    // the source map owns every input unit and gives each a non-overlapping range in one global
+    // byte space; it has no lookup from a global offset back to its unit or line/column, so each
+    // unit is parsed and reported on its own through its `UnitId`
+    let mut source_map = lexer::SourceMap::new();
+    let mut unit_ids = Vec::new();
+    for filename in &filenames {
+        match source_map.add_file(filename) {
+            Ok(id) => unit_ids.push(id),
             Err(e) => {
                 print_fatal_error(&format!("failed to read source file `{}`, {}", filename, e));
             }
         }
     }
+
+    for id in unit_ids {
+        let ast = parser::parse(source_map.get(id));
+        let diag = match error_format {
+            ErrorFormat::Human => ast.get_diagnostics(),
+            ErrorFormat::Json => ast.get_diagnostics_json(),
+        };
+        if let Some(diag) = diag {
+            eprintln!("{}", diag);
+        }
+    }
 }
@@ -0,0 +1,263 @@
+//! A lossless concrete-syntax-tree (CST) layer that sits alongside [`crate::ast`].
+//!
+//! Unlike the abstract tree, which references only the tokens that carry meaning and throws away
+//! the blank and comment tokens in between, the CST records every token a construct spans — the
+//! interleaved trivia included — so the original source can be reproduced byte-for-byte. The
+//! design follows rust-analyzer's green/red split: the [`GreenNode`] tree is a compact, position
+//! independent description of the syntax, and a [`Node`] is a cheap cursor that pairs a green node
+//! with its absolute offset so callers can ask for a [`TextRange`] without the green tree knowing
+//! where it lives.
+
+use std::rc::Rc;
+
+/// The syntactic category of a green node or leaf. Node kinds mirror the variants of
+/// [`crate::ast::AstNode`]; the two leaf kinds distinguish meaningful tokens from the blank and
+/// comment [`SyntaxKind::Trivia`] that the abstract tree discards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyntaxKind {
+    Module,
+    Definition,
+    ExprStatement,
+    If,
+    Int,
+    Identifier,
+    StringLiteral,
+    ArithmeticOrLogical,
+    Assign,
+    Negation,
+    Not,
+    Grouped,
+    Call,
+    Index,
+    Block,
+    Return,
+    While,
+    Loop,
+    Break,
+    Continue,
+    Match,
+    Let,
+    Error,
+    TypeAnno,
+    GenericAnno,
+    TupleAnno,
+    FuncAnno,
+    Pattern,
+    TuplePattern,
+    // a single meaningful token the parser kept (a keyword, operator, literal, ...)
+    Token,
+    // a run of whitespace, a newline, or a comment that the abstract tree skips over
+    Trivia,
+}
+
+impl SyntaxKind {
+    /// Whether this kind labels a leaf that the abstract tree never reaches.
+    pub fn is_trivia(self) -> bool {
+        matches!(self, SyntaxKind::Trivia)
+    }
+}
+
+/// A half-open byte range `[start, end)` into the compilation unit's raw content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextRange {
+    start: usize,
+    end: usize,
+}
+
+impl TextRange {
+    pub fn new(start: usize, end: usize) -> Self {
+        assert!(
+            start <= end,
+            "BUG: TextRange start {} exceeds end {}",
+            start,
+            end
+        );
+        Self { start, end }
+    }
+    pub fn start(&self) -> usize {
+        self.start
+    }
+    pub fn end(&self) -> usize {
+        self.end
+    }
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+    pub fn contains(&self, offset: usize) -> bool {
+        self.start <= offset && offset < self.end
+    }
+}
+
+impl std::fmt::Display for TextRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}..{}", self.start, self.end)
+    }
+}
+
+/// A leaf of the green tree: one token's kind paired with the exact source text it covered.
+#[derive(Debug)]
+pub struct GreenToken {
+    kind: SyntaxKind,
+    text: String,
+}
+
+impl GreenToken {
+    pub(crate) fn new(kind: SyntaxKind, text: String) -> Self {
+        Self { kind, text }
+    }
+    fn width(&self) -> usize {
+        self.text.len()
+    }
+}
+
+/// Either an interior node or a leaf token, the element type of a green node's child list.
+#[derive(Debug)]
+pub enum GreenChild {
+    Node(Rc<GreenNode>),
+    Token(GreenToken),
+}
+
+impl GreenChild {
+    fn width(&self) -> usize {
+        match self {
+            GreenChild::Node(node) => node.width,
+            GreenChild::Token(token) => token.width(),
+        }
+    }
+}
+
+/// A position-independent description of a construct and everything it spans, trivia included.
+#[derive(Debug)]
+pub struct GreenNode {
+    kind: SyntaxKind,
+    width: usize,
+    children: Vec<GreenChild>,
+}
+
+impl GreenNode {
+    pub(crate) fn new(kind: SyntaxKind, children: Vec<GreenChild>) -> Rc<Self> {
+        let width = children.iter().map(GreenChild::width).sum();
+        Rc::new(Self {
+            kind,
+            width,
+            children,
+        })
+    }
+}
+
+/// Either a subtree cursor or a token cursor, returned while walking a [`Node`]'s children.
+#[derive(Debug)]
+pub enum Element {
+    Node(Node),
+    Token(Token),
+}
+
+impl Element {
+    /// The covered range, regardless of whether this element is a node or a token.
+    pub fn text_range(&self) -> TextRange {
+        match self {
+            Element::Node(node) => node.text_range(),
+            Element::Token(token) => token.text_range(),
+        }
+    }
+}
+
+/// A cheap cursor into the green tree that remembers its absolute byte offset, so it can answer
+/// [`Node::text_range`] and stitch the source back together without the green node moving.
+#[derive(Debug, Clone)]
+pub struct Node {
+    green: Rc<GreenNode>,
+    offset: usize,
+}
+
+impl Node {
+    /// Wraps a green root at the given absolute byte offset (`0` for a whole compilation unit).
+    pub fn new(green: Rc<GreenNode>, offset: usize) -> Self {
+        Self { green, offset }
+    }
+    pub fn kind(&self) -> SyntaxKind {
+        self.green.kind
+    }
+    /// The exact byte range this node covers, trivia included.
+    pub fn text_range(&self) -> TextRange {
+        TextRange::new(self.offset, self.offset + self.green.width)
+    }
+    /// Reconstructs the original source text this node spans, byte-for-byte.
+    pub fn to_source(&self) -> String {
+        let mut out = String::with_capacity(self.green.width);
+        self.write_source(&mut out);
+        out
+    }
+    fn write_source(&self, out: &mut String) {
+        for child in &self.green.children {
+            match child {
+                GreenChild::Node(node) => Node::new(node.clone(), 0).write_source(out),
+                GreenChild::Token(token) => out.push_str(&token.text),
+            }
+        }
+    }
+    /// Every child in source order — interior nodes as well as token and trivia leaves.
+    pub fn children_with_tokens(&self) -> Vec<Element> {
+        let mut offset = self.offset;
+        self.green
+            .children
+            .iter()
+            .map(|child| {
+                let element = match child {
+                    GreenChild::Node(node) => Element::Node(Node::new(node.clone(), offset)),
+                    GreenChild::Token(token) => Element::Token(Token {
+                        kind: token.kind,
+                        text: token.text.clone(),
+                        offset,
+                    }),
+                };
+                offset += child.width();
+                element
+            })
+            .collect()
+    }
+    /// Only the interior-node children, skipping every leaf.
+    pub fn children(&self) -> Vec<Node> {
+        self.children_with_tokens()
+            .into_iter()
+            .filter_map(|element| match element {
+                Element::Node(node) => Some(node),
+                Element::Token(_) => None,
+            })
+            .collect()
+    }
+    /// Only the trivia leaves directly under this node — the blanks and comments the abstract
+    /// tree skipped over.
+    pub fn trivia(&self) -> Vec<Token> {
+        self.children_with_tokens()
+            .into_iter()
+            .filter_map(|element| match element {
+                Element::Token(token) if token.kind.is_trivia() => Some(token),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// A token cursor produced while walking a [`Node`]: its kind, covered text, and absolute offset.
+#[derive(Debug, Clone)]
+pub struct Token {
+    kind: SyntaxKind,
+    text: String,
+    offset: usize,
+}
+
+impl Token {
+    pub fn kind(&self) -> SyntaxKind {
+        self.kind
+    }
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+    pub fn text_range(&self) -> TextRange {
+        TextRange::new(self.offset, self.offset + self.text.len())
+    }
+}
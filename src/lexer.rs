@@ -1,8 +1,10 @@
 mod diags;
 mod get_tokens_utils;
 mod indices;
+mod source_map;
 
-pub(crate) use diags::DiagCtx;
+pub(crate) use diags::{DiagCtx, Diagnostics, SpanLabels};
+pub use source_map::{SourceMap, UnitId};
 use get_tokens_utils::{
     must_be_invalid_stuff, try_multi_byte_char, try_multi_byte_tokens, try_new_line,
     try_single_byte_token, try_string,
@@ -10,6 +12,10 @@ use get_tokens_utils::{
 use indices::{ByteIdx, ByteSpan, UcIdx, UcSpan};
 use unicode_segmentation::UnicodeSegmentation;
 
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct TokenIdx(usize);
 
@@ -22,7 +28,7 @@ impl TokenIdx {
     }
 }
 
-impl std::ops::Add<usize> for TokenIdx {
+impl core::ops::Add<usize> for TokenIdx {
     type Output = Self;
 
     fn add(self, rhs: usize) -> Self::Output {
@@ -30,13 +36,13 @@ impl std::ops::Add<usize> for TokenIdx {
     }
 }
 
-impl std::ops::AddAssign<usize> for TokenIdx {
+impl core::ops::AddAssign<usize> for TokenIdx {
     fn add_assign(&mut self, rhs: usize) {
         *self = *self + rhs;
     }
 }
 
-impl std::ops::Sub<usize> for TokenIdx {
+impl core::ops::Sub<usize> for TokenIdx {
     type Output = Self;
 
     fn sub(self, rhs: usize) -> Self::Output {
@@ -44,7 +50,7 @@ impl std::ops::Sub<usize> for TokenIdx {
     }
 }
 
-impl std::ops::SubAssign<usize> for TokenIdx {
+impl core::ops::SubAssign<usize> for TokenIdx {
     fn sub_assign(&mut self, rhs: usize) {
         *self = *self - rhs;
     }
@@ -68,14 +74,20 @@ pub(super) enum TokenKind {
     Plus,
     Minus,
     Star,
+    StarStar,
     Slash,
     Percent,
     Colon,
+    Comma,
+    Arrow,
+    FatArrow,
 
     LParen,
     RParen,
     LCurlyBrace,
     RCurlyBrace,
+    LBracket,
+    RBracket,
 
     Eq,
 
@@ -86,6 +98,9 @@ pub(super) enum TokenKind {
     EqEq,
     Ne,
 
+    AmpAmp,
+    PipePipe,
+
     Not,
 
     Identifier {
@@ -97,6 +112,12 @@ pub(super) enum TokenKind {
     KwIf,
     KwElse,
     KwReturn,
+    KwWhile,
+    KwLoop,
+    KwBreak,
+    KwContinue,
+    KwFn,
+    KwMatch,
 
     Invalid {
         msg: String,
@@ -106,8 +127,8 @@ pub(super) enum TokenKind {
     FakeTokenForInvalid,
 }
 
-impl std::fmt::Display for TokenKind {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+impl core::fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
         match self {
             TokenKind::Spaces { .. } => write!(f, "Spaces"),
             TokenKind::NewLine => write!(f, "NewLine"),
@@ -118,9 +139,13 @@ impl std::fmt::Display for TokenKind {
             TokenKind::Plus => write!(f, "Plus"),
             TokenKind::Minus => write!(f, "Minus"),
             TokenKind::Star => write!(f, "Star"),
+            TokenKind::StarStar => write!(f, "StarStar"),
             TokenKind::Slash => write!(f, "Slash"),
             TokenKind::Percent => write!(f, "Percentage"),
             TokenKind::Colon => write!(f, "Colon"),
+            TokenKind::Comma => write!(f, "Comma"),
+            TokenKind::Arrow => write!(f, "Arrow"),
+            TokenKind::FatArrow => write!(f, "FatArrow"),
             TokenKind::Eq => write!(f, "Eq"),
             TokenKind::Gt => write!(f, "Gt"),
             TokenKind::Ge => write!(f, "Ge"),
@@ -128,17 +153,27 @@ impl std::fmt::Display for TokenKind {
             TokenKind::Le => write!(f, "Le"),
             TokenKind::EqEq => write!(f, "EqEq"),
             TokenKind::Ne => write!(f, "Ne"),
+            TokenKind::AmpAmp => write!(f, "AmpAmp"),
+            TokenKind::PipePipe => write!(f, "PipePipe"),
             TokenKind::Not => write!(f, "Not"),
             TokenKind::LParen => write!(f, "LParen"),
             TokenKind::RParen => write!(f, "RParen"),
             TokenKind::LCurlyBrace => write!(f, "LCurlyBrace"),
             TokenKind::RCurlyBrace => write!(f, "RCurlyBrace"),
+            TokenKind::LBracket => write!(f, "LBracket"),
+            TokenKind::RBracket => write!(f, "RBracket"),
             TokenKind::Identifier { .. } => write!(f, "Identifier"),
             TokenKind::KwLet => write!(f, "KwLet"),
             TokenKind::KwVar => write!(f, "KwVar"),
             TokenKind::KwIf => write!(f, "KwIf"),
             TokenKind::KwElse => write!(f, "KwElse"),
             TokenKind::KwReturn => write!(f, "KwReturn"),
+            TokenKind::KwWhile => write!(f, "KwWhile"),
+            TokenKind::KwLoop => write!(f, "KwLoop"),
+            TokenKind::KwBreak => write!(f, "KwBreak"),
+            TokenKind::KwContinue => write!(f, "KwContinue"),
+            TokenKind::KwFn => write!(f, "KwFn"),
+            TokenKind::KwMatch => write!(f, "KwMatch"),
             TokenKind::Invalid { msg, .. } => write!(f, "{}", msg),
             TokenKind::FakeTokenForInvalid => write!(f, "FakeTokenForInvalid"),
         }
@@ -160,9 +195,13 @@ impl TokenKindRepr for TokenKind {
             TokenKind::Plus => String::from("+"),
             TokenKind::Minus => String::from("-"),
             TokenKind::Star => String::from("*"),
+            TokenKind::StarStar => String::from("**"),
             TokenKind::Slash => String::from("/"),
             TokenKind::Percent => String::from("%"),
             TokenKind::Colon => String::from(":"),
+            TokenKind::Comma => String::from(","),
+            TokenKind::Arrow => String::from("->"),
+            TokenKind::FatArrow => String::from("=>"),
             TokenKind::Eq => String::from("="),
             TokenKind::Gt => String::from(">"),
             TokenKind::Ge => String::from(">="),
@@ -170,16 +209,27 @@ impl TokenKindRepr for TokenKind {
             TokenKind::Le => String::from("<="),
             TokenKind::EqEq => String::from("=="),
             TokenKind::Ne => String::from("!="),
+            TokenKind::Not => String::from("!"),
+            TokenKind::AmpAmp => String::from("&&"),
+            TokenKind::PipePipe => String::from("||"),
             TokenKind::LParen => String::from("("),
             TokenKind::RParen => String::from(")"),
             TokenKind::LCurlyBrace => String::from("{"),
             TokenKind::RCurlyBrace => String::from("}"),
+            TokenKind::LBracket => String::from("["),
+            TokenKind::RBracket => String::from("]"),
             TokenKind::Identifier { name } => name.clone(),
             TokenKind::KwLet => String::from("let"),
             TokenKind::KwVar => String::from("var"),
             TokenKind::KwIf => String::from("if"),
             TokenKind::KwElse => String::from("else"),
             TokenKind::KwReturn => String::from("return"),
+            TokenKind::KwWhile => String::from("while"),
+            TokenKind::KwLoop => String::from("loop"),
+            TokenKind::KwBreak => String::from("break"),
+            TokenKind::KwContinue => String::from("continue"),
+            TokenKind::KwFn => String::from("fn"),
+            TokenKind::KwMatch => String::from("match"),
             _ => self.to_string(),
         }
     }
@@ -209,6 +259,28 @@ impl Token {
     pub(crate) fn get_kind(&self) -> &TokenKind {
         &self.kind
     }
+
+    // the error channel (`FakeTokenForInvalid`/`Invalid`) overlaps the spans of the real tokens it
+    // annotates, so lossless reconstruction skips it; every other token tiles the source exactly
+    fn is_error_channel(&self) -> bool {
+        matches!(
+            self.kind,
+            TokenKind::FakeTokenForInvalid | TokenKind::Invalid { .. }
+        )
+    }
+
+    // the half-open byte range this token covers in the compilation unit's raw content, used by
+    // the concrete-syntax-tree layer to compute absolute text ranges
+    pub(crate) fn get_byte_range(&self, cu: &CompilationUnit) -> (usize, usize) {
+        let span = self.uc_span.get_byte_span(cu).unwrap_or_else(|| {
+            panic!(
+                "BUG: failed to get byte span from token {:?} in {:?}",
+                self,
+                cu.get_origin()
+            )
+        });
+        (span.get_start().get(), span.get_inclusive_end().get() + 1)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -243,6 +315,23 @@ impl Tokens {
         self.0.iter()
     }
 
+    /// Reconstructs the source exactly, concatenating each token's original slice. For a cleanly
+    /// lexed unit the tokens tile the input with no gaps or overlap, so the result equals
+    /// `cu.raw_content` byte-for-byte — the foundation for whitespace- and comment-preserving
+    /// source-to-source rewrites. The diagnostic-only error channel is skipped because it overlaps
+    /// the spans of the tokens it annotates.
+    pub(crate) fn to_source(&self, cu: &CompilationUnit) -> String {
+        self.iter()
+            .filter(|token| !token.is_error_channel())
+            .map(|token| token.get_str(cu))
+            .collect()
+    }
+
+    fn has_lex_errors(&self) -> bool {
+        self.iter()
+            .any(|token| matches!(token.get_kind(), TokenKind::Invalid { .. }))
+    }
+
     pub(super) fn find_next_non_blank_token(
         &self,
         next_token_idx: TokenIdx,
@@ -260,7 +349,7 @@ impl Tokens {
     }
 }
 
-impl std::ops::Index<TokenIdx> for Tokens {
+impl core::ops::Index<TokenIdx> for Tokens {
     type Output = Token;
 
     fn index(&self, index: TokenIdx) -> &Self::Output {
@@ -273,6 +362,96 @@ impl std::ops::Index<TokenIdx> for Tokens {
     }
 }
 
+impl FromIterator<Token> for Tokens {
+    fn from_iter<I: IntoIterator<Item = Token>>(iter: I) -> Self {
+        let iter = iter.into_iter();
+        let mut tokens = Tokens::with_capacity(iter.size_hint().0);
+        for token in iter {
+            tokens.push_token(token);
+        }
+        tokens
+    }
+}
+
+/// A pull-based lexer: each [`Iterator::next`] runs exactly one `try_*` step against the
+/// compilation unit and streams out the token(s) it produced, so a consumer that stops early (a
+/// parser bailing on a fatal error, say) never pays to lex the rest of the input.
+///
+/// A single step occasionally produces more than one token — the error channel pushes a
+/// `FakeTokenForInvalid`/`Invalid` pair — so the stream buffers a step's output internally and
+/// hands it out one token at a time. Collecting the whole stream rebuilds the eager
+/// [`Tokens`] vec, preserving the original API and every token's global index.
+#[derive(Debug)]
+pub(crate) struct Lexer<'cu> {
+    cu: &'cu CompilationUnit,
+    uc_idx: UcIdx,
+    // tokens produced so far, kept whole so the `error_fake_token_idx` back-references the `try_*`
+    // steps record stay valid against the final stream
+    produced: Tokens,
+    emitted: usize,
+}
+
+impl<'cu> Lexer<'cu> {
+    pub(crate) fn new(cu: &'cu CompilationUnit) -> Self {
+        Self {
+            cu,
+            uc_idx: UcIdx::new(0),
+            produced: Tokens::with_capacity(cu.ucs.len()),
+            emitted: 0,
+        }
+    }
+
+    // runs one tokenizing step, appending its token(s) to `produced`; returns `false` at end of
+    // input. the branch order mirrors the original eager loop exactly.
+    fn step(&mut self) -> bool {
+        let cu = self.cu;
+        let Some(s) = cu.get_str(self.uc_idx) else {
+            return false;
+        };
+
+        // illegal multi-byte char
+        if let Some(new_uc_idx) = try_multi_byte_char(cu, &mut self.produced, self.uc_idx, s) {
+            self.uc_idx = new_uc_idx;
+            return true;
+        }
+
+        let c = s.chars().next().unwrap();
+        if let Some(new_uc_idx) = try_new_line(cu, &mut self.produced, self.uc_idx, c) {
+            self.uc_idx = new_uc_idx;
+        } else if let Some(new_uc_idx) =
+            try_multi_byte_tokens(cu, &mut self.produced, self.uc_idx, c)
+        {
+            self.uc_idx = new_uc_idx;
+        } else if let Some(new_uc_idx) = try_string(cu, &mut self.produced, self.uc_idx, c) {
+            self.uc_idx = new_uc_idx;
+        } else if let Some(new_uc_idx) =
+            try_single_byte_token(cu, &mut self.produced, self.uc_idx, c)
+        {
+            self.uc_idx = new_uc_idx;
+        } else {
+            self.uc_idx = must_be_invalid_stuff(cu, &mut self.produced, self.uc_idx, c);
+        }
+        true
+    }
+}
+
+impl Iterator for Lexer<'_> {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Token> {
+        loop {
+            if self.emitted < self.produced.len() {
+                let token = self.produced[TokenIdx::new(self.emitted)].clone();
+                self.emitted += 1;
+                return Some(token);
+            }
+            if !self.step() {
+                return None;
+            }
+        }
+    }
+}
+
 #[derive(Debug)]
 struct UcContent(Vec<ByteSpan>);
 
@@ -292,10 +471,20 @@ impl UcContent {
     fn get_byte_span(&self, uc_idx: UcIdx) -> Option<&ByteSpan> {
         self.0.get(uc_idx.get())
     }
+
+    // the number of graphemes whose byte offset falls in the half-open range `[from, to)`, used by
+    // the source map to turn a byte offset into a column
+    fn grapheme_count(&self, from: ByteIdx, to: ByteIdx) -> usize {
+        self.0
+            .iter()
+            .filter(|span| span.get_start() >= from && span.get_start() < to)
+            .count()
+    }
 }
 
 #[derive(Debug)]
 enum CompilationUnitKind {
+    #[cfg(feature = "std")]
     FromFile { path: std::path::PathBuf },
     FromString { mark: String },
 }
@@ -312,6 +501,7 @@ impl CompilationUnit {
         s.replace("\r\n", "\n")
     }
 
+    #[cfg(feature = "std")]
     pub fn from_file<P>(filename: P) -> Result<Self, String>
     where
         P: AsRef<std::path::Path>,
@@ -343,6 +533,7 @@ impl CompilationUnit {
 
     pub(crate) fn get_origin(&self) -> String {
         match &self.kind {
+            #[cfg(feature = "std")]
             CompilationUnitKind::FromFile { path } => format!("{}", path.display()),
             CompilationUnitKind::FromString { mark } => String::from(mark),
         }
@@ -356,38 +547,8 @@ impl CompilationUnit {
         self.raw_content.as_bytes().len()
     }
 
-    fn bytes_offset(&self, s: &str) -> usize {
-        s.as_ptr() as usize - self.raw_content.as_ptr() as usize
-    }
-
     pub(crate) fn get_tokens(&self) -> (Tokens, DiagCtx) {
-        let mut tokens = Tokens::with_capacity(self.ucs.len());
-        let mut uc_idx = UcIdx::new(0);
-
-        while let Some(s) = self.get_str(uc_idx) {
-            // illegal mutli-byte char
-            if let Some(new_uc_idx) = try_multi_byte_char(self, &mut tokens, uc_idx, s) {
-                uc_idx = new_uc_idx;
-                continue;
-            }
-
-            let c = s.chars().next().unwrap();
-            if let Some(new_uc_idx) = try_new_line(self, &mut tokens, uc_idx, c) {
-                // new line
-                uc_idx = new_uc_idx;
-            } else if let Some(new_uc_idx) = try_multi_byte_tokens(self, &mut tokens, uc_idx, c) {
-                // multi-char tokens
-                uc_idx = new_uc_idx;
-            } else if let Some(new_uc_idx) = try_string(self, &mut tokens, uc_idx, c) {
-                // string
-                uc_idx = new_uc_idx;
-            } else if let Some(new_uc_idx) = try_single_byte_token(self, &mut tokens, uc_idx, c) {
-                // single-char tokens
-                uc_idx = new_uc_idx;
-            } else {
-                uc_idx = must_be_invalid_stuff(self, &mut tokens, uc_idx, c);
-            }
-        }
+        let tokens: Tokens = Lexer::new(self).collect();
 
         let diag_ctx = {
             let mut diag_ctx = DiagCtx::with_capacity(tokens.len() / 25);
@@ -399,6 +560,14 @@ impl CompilationUnit {
             diag_ctx
         };
 
+        // a clean lex is lossless: the tokens must reproduce the input exactly. error tokens
+        // overlap the spans they annotate, so the guarantee only holds when lexing found no errors.
+        debug_assert!(
+            tokens.has_lex_errors() || tokens.to_source(self) == self.raw_content,
+            "BUG: token stream does not round-trip to its source in {}",
+            self.get_origin()
+        );
+
         (tokens, diag_ctx)
     }
 }
@@ -1,7 +1,16 @@
-use super::{ByteIdx, ByteSpan, CompilationUnit, TokenIdx, Tokens};
+// the spanned-diagnostic API below is built out ahead of the call sites that replace the bare
+// `TokenKind::Invalid { msg }` stringification, so some constructors have no caller yet
+#![allow(dead_code)]
+
+use super::{ByteIdx, ByteSpan, CompilationUnit, TokenIdx, Tokens, UcSpan};
+use core::fmt::Write;
 use unicode_segmentation::UnicodeSegmentation;
 use unicode_width::UnicodeWidthStr;
 
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
 #[derive(Debug, Clone, Copy)]
 struct LineNr(usize);
 
@@ -21,20 +30,40 @@ impl ColNr {
     fn new(i: usize) -> Self {
         Self(i)
     }
+    fn get(&self) -> usize {
+        self.0
+    }
 }
 
 #[derive(Debug)]
 pub(super) struct SingleLineDiagnostic<'cu> {
     line: &'cu str,
     line_nr: LineNr,
-    _col_nr: ColNr,
+    // the 1-based grapheme column where this line's marked run begins: the error carets when the
+    // line carries the error, otherwise the context underline
+    col_nr: ColNr,
+    // whether this line carries error carets; the first such line is where the error span starts,
+    // so the position header anchors there rather than on a leading context line
+    is_error_line: bool,
     leading_width: usize,
     ctx_width: usize,
     error_width: usize,
+    // short messages for the secondary (context) and primary (error) runs, each printed on its own
+    // row beneath the carets, aligned to the start of the run it describes
+    ctx_label: Option<String>,
+    error_label: Option<String>,
+}
+
+impl SingleLineDiagnostic<'_> {
+    // the 1-based source line this run sits on; exposed so a test harness (or position-header
+    // renderer) can correlate a rendered diagnostic back to the line it points at
+    pub(crate) fn line_nr(&self) -> usize {
+        self.line_nr.get()
+    }
 }
 
-impl std::fmt::Display for SingleLineDiagnostic<'_> {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+impl core::fmt::Display for SingleLineDiagnostic<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
         write!(f, "{: >5}|{}", self.line_nr.get(), self.line)?;
         if !self.line.ends_with('\n') {
             writeln!(f)?;
@@ -51,6 +80,29 @@ impl std::fmt::Display for SingleLineDiagnostic<'_> {
                 ctx_width = self.ctx_width,
                 error_width = self.error_width
             )?;
+            // one label row per run, aligned so the label sits under the first glyph of the run it
+            // describes: the error (primary) carets start after the leading text and context run,
+            // the context (secondary) run right after the leading text
+            if let Some(label) = &self.error_label {
+                writeln!(
+                    f,
+                    "{: >5}|{: >pad$}{}",
+                    "",
+                    "",
+                    label,
+                    pad = self.leading_width + self.ctx_width
+                )?;
+            }
+            if let Some(label) = &self.ctx_label {
+                writeln!(
+                    f,
+                    "{: >5}|{: >pad$}{}",
+                    "",
+                    "",
+                    label,
+                    pad = self.leading_width
+                )?;
+            }
         }
         Ok(())
     }
@@ -71,10 +123,28 @@ impl<'cu> Diagnostics<'cu> {
     fn is_empty(&self) -> bool {
         self.0.is_empty()
     }
+
+    /// The 1-based line of the first rendered run. That run is a leading context line when the
+    /// context starts above the error; `primary_position` gives the error's own line. Used to
+    /// attach a recoverable line number to the emitted text.
+    pub(crate) fn primary_line_nr(&self) -> Option<usize> {
+        self.0.first().map(SingleLineDiagnostic::line_nr)
+    }
+
+    /// The 1-based `(line, column)` of the primary error span: the first line the error starts on,
+    /// or — for a context-only diagnostic with no error caret — the first rendered run. Column is
+    /// grapheme-cluster based so multibyte and wide characters report the visually correct offset.
+    pub(crate) fn primary_position(&self) -> Option<(usize, usize)> {
+        self.0
+            .iter()
+            .find(|diag| diag.is_error_line)
+            .or_else(|| self.0.first())
+            .map(|diag| (diag.line_nr.get(), diag.col_nr.get()))
+    }
 }
 
-impl std::fmt::Display for Diagnostics<'_> {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+impl core::fmt::Display for Diagnostics<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
         for diag in &self.0 {
             write!(f, "{}", diag)?;
         }
@@ -97,28 +167,40 @@ impl<'cu> DiagCtx<'cu> {
         self.line_starts.push((line_starts_byte_idx, line));
     }
 
-    fn get_line(&self, start_byte_idx: ByteIdx) -> (LineNr, &'cu str) {
-        self.line_starts
-            .iter()
-            .zip(
-                self.line_starts
-                    .iter()
-                    .skip(1)
-                    .chain(std::iter::repeat(&(ByteIdx::MAX, ""))),
-            )
-            .enumerate()
-            .find(|(_, ((idx1, _), (idx2, _)))| *idx1 <= start_byte_idx && start_byte_idx < *idx2)
-            .map(|(line_nr, ((_, line), _))| (LineNr::new(line_nr + 1), *line))
-            .unwrap_or_else(|| panic!("BUG: no line found"))
+    // the line containing a byte offset, found by binary search over the sorted line-start table.
+    // Returns the 1-based line number, the byte offset the line begins at, and the line slice, so
+    // callers need not recompute the line's own offset from `cu`.
+    fn get_line(&self, start_byte_idx: ByteIdx) -> (LineNr, ByteIdx, &'cu str) {
+        let line_idx = self
+            .line_starts
+            .partition_point(|(line_start, _)| *line_start <= start_byte_idx)
+            .checked_sub(1)
+            .unwrap_or_else(|| panic!("BUG: no line found"));
+        let (line_start_byte_idx, line) = self.line_starts[line_idx];
+        (LineNr::new(line_idx + 1), line_start_byte_idx, line)
+    }
+
+    // the 1-based line and grapheme column of a byte offset, shared by the caret renderer and the
+    // machine-readable position output
+    fn line_col_of(&self, byte_idx: ByteIdx) -> (LineNr, ColNr) {
+        let (line_nr, line_start_byte_idx, line) = self.get_line(byte_idx);
+        let offset = byte_idx.get() - line_start_byte_idx.get();
+        let col = line[..offset].grapheme_indices(true).count() + 1;
+        (line_nr, ColNr::new(col))
     }
 
     fn get_diags(
         &self,
         mut ctx_start_byte_idx: Option<ByteIdx>,
         mut error_byte_span: Option<ByteSpan>,
+        labels: SpanLabels,
         cu: &'cu CompilationUnit,
     ) -> Diagnostics<'cu> {
         let mut diags = Diagnostics::new();
+        // each label is attached to the first rendered line its run appears on, then cleared so a
+        // run split across lines prints its label only once
+        let mut ctx_label_pending = labels.ctx;
+        let mut error_label_pending = labels.error;
 
         loop {
             let start_byte_idx = match (ctx_start_byte_idx, error_byte_span) {
@@ -136,8 +218,8 @@ impl<'cu> DiagCtx<'cu> {
             if start_byte_idx.get() == cu.bytes_len() {
                 break;
             }
-            let (line_nr, line) = self.get_line(start_byte_idx);
-            let line_start_byte_idx = cu.bytes_offset(line);
+            let (line_nr, line_start_byte_idx, line) = self.get_line(start_byte_idx);
+            let line_start_byte_idx = line_start_byte_idx.get();
 
             let (leading_str, ctx_str, error_str) = match ctx_start_byte_idx {
                 // has ctx
@@ -215,15 +297,38 @@ impl<'cu> DiagCtx<'cu> {
             let leading_width = UnicodeWidthStr::width(leading_str);
             let error_width = UnicodeWidthStr::width(error_str);
             let ctx_width = UnicodeWidthStr::width(ctx_str);
-            let col_nr = ColNr::new(leading_str.grapheme_indices(true).count() + 1);
+            // the carets (`error_str`) start after the leading text and any context run, so the
+            // error column counts graphemes through both; a context-only line has no error run and
+            // anchors at the start of its underline instead
+            let is_error_line = !error_str.is_empty();
+            let marked_start = if is_error_line {
+                leading_str.grapheme_indices(true).count() + ctx_str.grapheme_indices(true).count()
+            } else {
+                leading_str.grapheme_indices(true).count()
+            };
+            let col_nr = ColNr::new(marked_start + 1);
+
+            let ctx_label = if ctx_width > 0 {
+                ctx_label_pending.take()
+            } else {
+                None
+            };
+            let error_label = if error_width > 0 {
+                error_label_pending.take()
+            } else {
+                None
+            };
 
             diags.push(SingleLineDiagnostic {
                 line,
                 line_nr,
-                _col_nr: col_nr,
+                col_nr,
+                is_error_line,
                 leading_width,
                 ctx_width,
                 error_width,
+                ctx_label,
+                error_label,
             });
         }
 
@@ -237,28 +342,31 @@ impl<'cu> DiagCtx<'cu> {
     pub(crate) fn get_diag_with_error_token(
         &self,
         error_token_idx: TokenIdx,
+        labels: SpanLabels,
         tokens: &'cu Tokens,
         cu: &'cu CompilationUnit,
     ) -> Diagnostics<'cu> {
         let error_token = &tokens[error_token_idx];
 
-        self.get_diags(None, error_token.uc_span.get_byte_span(cu), cu)
+        self.get_diags(None, error_token.uc_span.get_byte_span(cu), labels, cu)
     }
     pub(crate) fn get_diag_with_ctx_token(
         &self,
         ctx_token_idx: TokenIdx,
+        labels: SpanLabels,
         tokens: &'cu Tokens,
         cu: &'cu CompilationUnit,
     ) -> Diagnostics<'cu> {
         let ctx_token = &tokens[ctx_token_idx];
         let ctx_token_start_byte_idx = ctx_token.uc_span.get_start_byte_idx_unchecked(cu);
 
-        self.get_diags(Some(ctx_token_start_byte_idx), None, cu)
+        self.get_diags(Some(ctx_token_start_byte_idx), None, labels, cu)
     }
     pub(crate) fn get_diag_with_ctx_and_error_tokens(
         &self,
         ctx_token_idx: TokenIdx,
         error_token_idx: TokenIdx,
+        labels: SpanLabels,
         tokens: &'cu Tokens,
         cu: &'cu CompilationUnit,
     ) -> Diagnostics<'cu> {
@@ -270,7 +378,233 @@ impl<'cu> DiagCtx<'cu> {
         self.get_diags(
             Some(ctx_start_byte_idx),
             error_token.uc_span.get_byte_span(cu),
+            labels,
             cu,
         )
     }
 }
+
+/// The short per-run messages attached to a caret diagnostic: `ctx` labels the secondary
+/// (context, `~`) run, `error` the primary (`^`) run. Either may be absent, in which case that
+/// run renders as a bare underline.
+#[derive(Debug, Default)]
+pub(crate) struct SpanLabels {
+    ctx: Option<String>,
+    error: Option<String>,
+}
+
+impl SpanLabels {
+    /// No labels — renders the bare `~~~^^^` underline.
+    pub(crate) fn none() -> Self {
+        Self::default()
+    }
+    /// Labels only the primary error run.
+    pub(crate) fn error(msg: impl Into<String>) -> Self {
+        Self {
+            ctx: None,
+            error: Some(msg.into()),
+        }
+    }
+    /// Labels both the secondary context run and the primary error run.
+    pub(crate) fn ctx_and_error(ctx: impl Into<String>, error: impl Into<String>) -> Self {
+        Self {
+            ctx: Some(ctx.into()),
+            error: Some(error.into()),
+        }
+    }
+}
+
+/// How serious a [`Diagnostic`] is, in the spirit of codespan-reporting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Severity {
+    Error,
+    Warning,
+    Note,
+    Help,
+}
+
+impl Severity {
+    fn as_str(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Note => "note",
+            Severity::Help => "help",
+        }
+    }
+}
+
+/// Whether a [`Label`] marks the span the diagnostic is really about (`Primary`, underlined with
+/// `^`) or a piece of supporting context (`Secondary`, underlined with `-`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LabelStyle {
+    Primary,
+    Secondary,
+}
+
+/// A span of source with a message attached, rendered as an underline beneath the offending line.
+#[derive(Debug)]
+pub(crate) struct Label {
+    style: LabelStyle,
+    span: UcSpan,
+    message: String,
+}
+
+impl Label {
+    pub(super) fn primary(span: UcSpan, message: impl Into<String>) -> Self {
+        Self {
+            style: LabelStyle::Primary,
+            span,
+            message: message.into(),
+        }
+    }
+    pub(super) fn secondary(span: UcSpan, message: impl Into<String>) -> Self {
+        Self {
+            style: LabelStyle::Secondary,
+            span,
+            message: message.into(),
+        }
+    }
+    fn caret(&self) -> char {
+        match self.style {
+            LabelStyle::Primary => '^',
+            LabelStyle::Secondary => '-',
+        }
+    }
+}
+
+/// A severity, a headline message, and one or more [`Label`]s; the first primary label anchors the
+/// `file:line:column` location line.
+#[derive(Debug)]
+pub(crate) struct Diagnostic {
+    severity: Severity,
+    message: String,
+    labels: Vec<Label>,
+}
+
+impl Diagnostic {
+    pub(crate) fn new(severity: Severity, message: impl Into<String>) -> Self {
+        Self {
+            severity,
+            message: message.into(),
+            labels: Vec::new(),
+        }
+    }
+    pub(crate) fn with_label(mut self, label: Label) -> Self {
+        self.labels.push(label);
+        self
+    }
+    // the span whose location heads the rendered output: the first primary label, or failing that
+    // the first label of any kind
+    fn anchor(&self) -> Option<&Label> {
+        self.labels
+            .iter()
+            .find(|label| label.style == LabelStyle::Primary)
+            .or_else(|| self.labels.first())
+    }
+}
+
+impl<'cu> DiagCtx<'cu> {
+    // the zero-based index of the line containing a byte offset, found by binary search over the
+    // sorted line table
+    fn line_index(&self, byte_idx: ByteIdx) -> usize {
+        let byte = byte_idx.get();
+        self.line_starts
+            .partition_point(|(start, _)| start.get() <= byte)
+            .saturating_sub(1)
+    }
+
+    fn line_text(&self, line_idx: usize) -> &'cu str {
+        self.line_starts[line_idx].1
+    }
+
+    fn line_start_byte(&self, line_idx: usize) -> usize {
+        self.line_starts[line_idx].0.get()
+    }
+
+    /// Renders a [`Diagnostic`] into the familiar gutter/underline form: a severity headline, the
+    /// `file:line:column` of the primary span, then every label's source line with a caret row
+    /// aligned beneath it by display width.
+    pub(crate) fn render(&self, diag: &Diagnostic, cu: &'cu CompilationUnit) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "{}: {}", diag.severity.as_str(), diag.message);
+
+        if let Some(anchor) = diag.anchor() {
+            if let Some(span) = anchor.span.get_byte_span(cu) {
+                let line_idx = self.line_index(span.get_start());
+                let column = self.display_width(line_idx, span.get_start().get()) + 1;
+                let _ = writeln!(
+                    out,
+                    "  --> {}:{}:{}",
+                    cu.get_origin(),
+                    line_idx + 1,
+                    column
+                );
+            }
+        }
+
+        for label in &diag.labels {
+            self.render_label(&mut out, label, cu);
+        }
+        out
+    }
+
+    // the display width (not byte length) of the graphemes on `line_idx` that precede `byte`, so
+    // tabs and wide CJK characters push the caret to the right place
+    fn display_width(&self, line_idx: usize, byte: usize) -> usize {
+        let line = self.line_text(line_idx);
+        let offset = byte - self.line_start_byte(line_idx);
+        UnicodeWidthStr::width(&line[..offset])
+    }
+
+    // the 1-based line and grapheme column of a byte offset, used by the machine-readable JSON
+    // renderer where a caret-aligned display width would be the wrong unit to report
+    pub(crate) fn line_col_of_byte(&self, byte: usize) -> (usize, usize) {
+        let (line_nr, col_nr) = self.line_col_of(ByteIdx::new(byte));
+        (line_nr.get(), col_nr.get())
+    }
+
+    fn render_label(&self, out: &mut String, label: &Label, cu: &'cu CompilationUnit) {
+        let Some(span) = label.span.get_byte_span(cu) else {
+            return;
+        };
+        let start = span.get_start().get();
+        let end = span.get_inclusive_end().get();
+        let first = self.line_index(span.get_start());
+        let last = self.line_index(span.get_inclusive_end());
+
+        for line_idx in first..=last {
+            let line = self.line_text(line_idx);
+            let content = line.strip_suffix('\n').unwrap_or(line);
+            let line_start = self.line_start_byte(line_idx);
+            // the byte range of `content` this label underlines: from the span start (or column 0
+            // on a continuation line) to the span end (or end-of-line on a non-final line)
+            let lo = if line_idx == first {
+                start - line_start
+            } else {
+                0
+            };
+            let hi = if line_idx == last {
+                (end - line_start + 1).min(content.as_bytes().len())
+            } else {
+                content.as_bytes().len()
+            };
+            let leading = UnicodeWidthStr::width(&content[..lo]);
+            let width = UnicodeWidthStr::width(&content[lo..hi]).max(1);
+            let trailing = if line_idx == last && !label.message.is_empty() {
+                format!(" {}", label.message)
+            } else {
+                String::new()
+            };
+            let _ = writeln!(out, "{: >5}|{}", line_idx + 1, content);
+            let _ = writeln!(
+                out,
+                "{: >5}|{}{}{}",
+                "",
+                " ".repeat(leading),
+                label.caret().to_string().repeat(width),
+                trailing
+            );
+        }
+    }
+}
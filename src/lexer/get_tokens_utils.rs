@@ -1,5 +1,10 @@
 use super::{indices::UcIdx, indices::UcSpan, CompilationUnit, TokenIdx, TokenKind, Tokens};
 
+use alloc::borrow::ToOwned;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
 pub(super) trait StringLike {
     fn is_new_line(&self) -> bool;
 }
@@ -43,67 +48,117 @@ fn find_string_end(cu: &CompilationUnit, mut start: UcIdx) -> Option<UcIdx> {
     possible_new_line
 }
 
-fn take_unicode(
-    cu: &CompilationUnit,
-    after_rquote_uc_idx: UcIdx,
-    lbrace_uc_idx: UcIdx,
-) -> Result<(UcIdx, String), TakeStringError> {
-    // start with {
+// a single escape either decodes to some text or fails with a diagnostic anchored at a source
+// position; both carry the index to resume scanning from so the caller can keep collecting errors
+struct EscapeOutcome {
+    resume_uc_idx: UcIdx,
+    result: Result<String, (UcIdx, String)>,
+}
+
+// reads a `\u{...}` body, with `lbrace_uc_idx` pointing at the `{` that should follow `\u`;
+// requires 1-6 hex digits naming a valid, non-surrogate code point
+fn take_unicode(cu: &CompilationUnit, lbrace_uc_idx: UcIdx) -> EscapeOutcome {
     if cu.get_str(lbrace_uc_idx).map_or(true, |s| s != "{") {
-        return Err(TakeStringError {
-            error_uc_idx: lbrace_uc_idx,
-            msg: "unicode should be in the format of \\u{...}".to_owned(),
-            next_uc_idx: after_rquote_uc_idx,
-        });
+        return EscapeOutcome {
+            resume_uc_idx: lbrace_uc_idx,
+            result: Err((lbrace_uc_idx, "unicode escape must be `\\u{...}`".to_owned())),
+        };
     }
-    let mut hex_num_uc_idx = lbrace_uc_idx + 1;
 
-    // take hex numbers
-    while let Some(s) = cu.get_str(hex_num_uc_idx) {
-        if s == "}" {
-            break;
-        }
-        if s.len() != 1 || !s.chars().next().unwrap().is_ascii_hexdigit() {
-            return Err(TakeStringError {
-                error_uc_idx: hex_num_uc_idx,
-                msg: format!(
-                    "only hex numbers are allowed in unicode sequence, `{}` is not allowed",
-                    s
-                ),
-                next_uc_idx: after_rquote_uc_idx,
-            });
+    // scan up to the closing `}`, remembering the first non-hex character so the whole escape can
+    // be consumed before its diagnostic is reported
+    let mut hex_num_uc_idx = lbrace_uc_idx + 1;
+    let mut bad_char = None;
+    let rbrace_uc_idx = loop {
+        match cu.get_str(hex_num_uc_idx) {
+            Some("}") => break hex_num_uc_idx,
+            None => {
+                return EscapeOutcome {
+                    resume_uc_idx: hex_num_uc_idx,
+                    result: Err((
+                        lbrace_uc_idx,
+                        "unterminated unicode escape, expected `}`".to_owned(),
+                    )),
+                };
+            }
+            Some(s) if s.len() == 1 && s.chars().next().unwrap().is_ascii_hexdigit() => {
+                hex_num_uc_idx += 1;
+            }
+            Some(s) => {
+                bad_char.get_or_insert((
+                    hex_num_uc_idx,
+                    format!("invalid character `{}` in unicode escape", s),
+                ));
+                hex_num_uc_idx += 1;
+            }
         }
-        hex_num_uc_idx += 1;
+    };
+    let resume_uc_idx = rbrace_uc_idx + 1;
+
+    if let Some(err) = bad_char {
+        return EscapeOutcome {
+            resume_uc_idx,
+            result: Err(err),
+        };
     }
-    let rbrace_uc_idx = hex_num_uc_idx;
 
-    // empty {}?
+    let err = |msg: String| EscapeOutcome {
+        resume_uc_idx,
+        result: Err((lbrace_uc_idx + 1, msg)),
+    };
     if rbrace_uc_idx == lbrace_uc_idx + 1 {
-        return Err(TakeStringError {
-            error_uc_idx: rbrace_uc_idx,
-            msg: "unicode should be in the format of \\u{...}, cannot be empty between `{}`"
-                .to_owned(),
-            next_uc_idx: after_rquote_uc_idx,
-        });
+        return err("empty unicode escape, expected 1-6 hex digits".to_owned());
     }
 
-    // convert hex to char
-    let mut s = cu
+    let digits = cu
         .get_str(UcSpan::new(lbrace_uc_idx + 1, rbrace_uc_idx - 1))
-        .unwrap()
-        .to_owned();
-    if s.len() % 2 != 0 {
-        s = format!("0{}", s);
-    }
-    let unicode_err_fn = || TakeStringError {
-        error_uc_idx: lbrace_uc_idx + 1,
-        msg: format!("`{}` is not a valid unicode code point", s),
-        next_uc_idx: after_rquote_uc_idx,
-    };
-    let n = u32::from_str_radix(&s, 16).map_err(|_| unicode_err_fn())?;
-    let c = char::from_u32(n).ok_or_else(unicode_err_fn)?;
+        .unwrap_or("");
+    if digits.len() > 6 {
+        return err("overlong unicode escape, expected at most 6 hex digits".to_owned());
+    }
+    match u32::from_str_radix(digits, 16).ok().and_then(char::from_u32) {
+        Some(c) => EscapeOutcome {
+            resume_uc_idx,
+            result: Ok(c.to_string()),
+        },
+        None => err(format!("`{}` is not a valid unicode code point", digits)),
+    }
+}
 
-    Ok((rbrace_uc_idx, c.to_string()))
+// reads the two hex digits of a `\xNN` escape, with `start` pointing just past the `x`; exactly
+// two hex digits are required and the value must be at most `0x7F`, matching the ascii-only range
+// of `\x`. each diagnostic is anchored at the offending digit so the line/column machinery can
+// underline it precisely.
+fn take_hex_escape(cu: &CompilationUnit, start: UcIdx) -> EscapeOutcome {
+    let mut value = 0u32;
+    let mut idx = start;
+    for _ in 0..2 {
+        match cu.get_str(idx) {
+            Some(s) if s.len() == 1 && s.chars().next().unwrap().is_ascii_hexdigit() => {
+                value = value * 16 + s.chars().next().unwrap().to_digit(16).unwrap();
+                idx += 1;
+            }
+            _ => {
+                return EscapeOutcome {
+                    resume_uc_idx: idx,
+                    result: Err((idx, "numeric character escape needs exactly two hex digits".to_owned())),
+                };
+            }
+        }
+    }
+    if value > 0x7F {
+        return EscapeOutcome {
+            resume_uc_idx: idx,
+            result: Err((
+                start,
+                "out of range hex escape, must be in 0x00..=0x7F".to_owned(),
+            )),
+        };
+    }
+    EscapeOutcome {
+        resume_uc_idx: idx,
+        result: Ok(char::from_u32(value).unwrap().to_string()),
+    }
 }
 
 struct TakeStringError {
@@ -112,68 +167,115 @@ struct TakeStringError {
     next_uc_idx: UcIdx,
 }
 
-fn take_string(cu: &CompilationUnit, mut start: UcIdx) -> Result<(UcIdx, String), TakeStringError> {
+// decodes a string literal's body starting at `start` (the first character after the opening
+// quote), returning the closing quote's index, the decoded content, and one diagnostic per invalid
+// escape. Only an unterminated literal is fatal; bad escapes are collected so a single literal can
+// report all of its problems at once, each decoded as U+FFFD so scanning can continue.
+fn take_string(
+    cu: &CompilationUnit,
+    mut start: UcIdx,
+) -> Result<(UcIdx, String, Vec<(UcIdx, String)>), TakeStringError> {
     let lquote_uc_idx = start - 1;
     let unterminated_err_fn = || TakeStringError {
         error_uc_idx: lquote_uc_idx,
         msg: "unterminated string literal".to_owned(),
         next_uc_idx: UcIdx::new(cu.ucs.len()),
     };
-    let Some(rquote_uc_idx) = find_string_end(cu, start) else {
+    if find_string_end(cu, start).is_none() {
         return Err(unterminated_err_fn());
-    };
-    let after_rquote_uc_idx = rquote_uc_idx + 1;
+    }
 
     let mut content = String::with_capacity(50);
-
-    let escaped_chars: std::collections::HashMap<&str, &str> = [
-        ("\\", "\\"),
-        ("\"", "\""),
-        ("n", "\n"),
-        ("r", "\r"),
-        ("t", "\t"),
-        ("0", "\0"),
-    ]
-    .into_iter()
-    .collect();
-    let mut in_escape = false;
+    let mut escape_errors = Vec::new();
     while let Some(s) = cu.get_str(start) {
-        if in_escape {
-            if s == "u" {
-                let (new_start, chunk) = take_unicode(cu, after_rquote_uc_idx, start + 1)?;
-                start = new_start + 1;
-                content.push_str(&chunk);
-            } else if escaped_chars.contains_key(&s) {
-                start += 1;
-                content.push_str(escaped_chars[&s]);
-            } else {
-                return Err(TakeStringError {
-                    error_uc_idx: start,
-                    msg: format!("invalid escape char `{}`", s),
-                    next_uc_idx: after_rquote_uc_idx,
-                });
-            }
-            in_escape = false;
-            continue;
-        } else if s == r#"""# {
-            return Ok((start, content));
+        if s == r#"""# {
+            return Ok((start, content, escape_errors));
         }
-        if s == "\\" {
-            in_escape = true;
-        } else {
+        if s != "\\" {
             content.push_str(s);
+            start += 1;
+            continue;
+        }
+
+        // `start` is the backslash; decide what follows it
+        let backslash_uc_idx = start;
+        match cu.get_str(start + 1) {
+            Some("\\") => {
+                content.push('\\');
+                start += 2;
+            }
+            Some(r#"""#) => {
+                content.push('"');
+                start += 2;
+            }
+            Some("'") => {
+                content.push('\'');
+                start += 2;
+            }
+            Some("n") => {
+                content.push('\n');
+                start += 2;
+            }
+            Some("r") => {
+                content.push('\r');
+                start += 2;
+            }
+            Some("t") => {
+                content.push('\t');
+                start += 2;
+            }
+            Some("0") => {
+                content.push('\0');
+                start += 2;
+            }
+            Some("x") => {
+                let EscapeOutcome {
+                    resume_uc_idx,
+                    result,
+                } = take_hex_escape(cu, start + 2);
+                start = resume_uc_idx;
+                push_escape_result(&mut content, &mut escape_errors, result);
+            }
+            Some("u") => {
+                let EscapeOutcome {
+                    resume_uc_idx,
+                    result,
+                } = take_unicode(cu, start + 2);
+                start = resume_uc_idx;
+                push_escape_result(&mut content, &mut escape_errors, result);
+            }
+            Some(other) => {
+                escape_errors
+                    .push((backslash_uc_idx, format!("unknown character escape `{}`", other)));
+                content.push('\u{FFFD}');
+                start += 2;
+            }
+            None => break,
         }
-        start += 1;
     }
 
     Err(unterminated_err_fn())
 }
 
+// appends a decoded escape to the content, or records its diagnostic and substitutes U+FFFD so
+// the decoded string stays usable for everything except reporting
+fn push_escape_result(
+    content: &mut String,
+    escape_errors: &mut Vec<(UcIdx, String)>,
+    result: Result<String, (UcIdx, String)>,
+) {
+    match result {
+        Ok(decoded) => content.push_str(&decoded),
+        Err(err) => {
+            escape_errors.push(err);
+            content.push('\u{FFFD}');
+        }
+    }
+}
+
 fn get_single_char_token_kind(c: char) -> Option<TokenKind> {
     match c {
         '+' => Some(TokenKind::Plus),
-        '-' => Some(TokenKind::Minus),
-        '*' => Some(TokenKind::Star),
         '/' => Some(TokenKind::Slash),
         '%' => Some(TokenKind::Percent),
         '(' => Some(TokenKind::LParen),
@@ -181,7 +283,10 @@ fn get_single_char_token_kind(c: char) -> Option<TokenKind> {
         ';' => Some(TokenKind::SemiColon),
         '{' => Some(TokenKind::LCurlyBrace),
         '}' => Some(TokenKind::RCurlyBrace),
+        '[' => Some(TokenKind::LBracket),
+        ']' => Some(TokenKind::RBracket),
         ':' => Some(TokenKind::Colon),
+        ',' => Some(TokenKind::Comma),
         _ => None,
     }
 }
@@ -193,6 +298,12 @@ fn get_keyword(s: &str) -> Option<TokenKind> {
         "if" => Some(TokenKind::KwIf),
         "else" => Some(TokenKind::KwElse),
         "return" => Some(TokenKind::KwReturn),
+        "while" => Some(TokenKind::KwWhile),
+        "loop" => Some(TokenKind::KwLoop),
+        "break" => Some(TokenKind::KwBreak),
+        "continue" => Some(TokenKind::KwContinue),
+        "fn" => Some(TokenKind::KwFn),
+        "match" => Some(TokenKind::KwMatch),
         _ => None,
     }
 }
@@ -211,6 +322,29 @@ pub(crate) fn try_new_line(
     Some(uc_idx + 1)
 }
 
+// code points that are easy to paste in from a word processor or non-English keyboard and that
+// visually resemble an ASCII token. each entry is `(confusable, unicode name, intended ascii)`, in
+// the spirit of rustc's `unicode_chars` table; the list is short enough to scan linearly.
+static CONFUSABLES: &[(char, &str, &str)] = &[
+    ('\u{201C}', "LEFT DOUBLE QUOTATION MARK", "\""),
+    ('\u{201D}', "RIGHT DOUBLE QUOTATION MARK", "\""),
+    ('\u{2018}', "LEFT SINGLE QUOTATION MARK", "'"),
+    ('\u{2019}', "RIGHT SINGLE QUOTATION MARK", "'"),
+    ('\u{FF08}', "FULLWIDTH LEFT PARENTHESIS", "("),
+    ('\u{FF09}', "FULLWIDTH RIGHT PARENTHESIS", ")"),
+    ('\u{2013}', "EN DASH", "-"),
+    ('\u{2014}', "EM DASH", "-"),
+    ('\u{FF1B}', "FULLWIDTH SEMICOLON", ";"),
+    ('\u{00D7}', "MULTIPLICATION SIGN", "*"),
+];
+
+fn find_confusable(c: char) -> Option<(&'static str, &'static str)> {
+    CONFUSABLES
+        .iter()
+        .find(|(confusable, ..)| *confusable == c)
+        .map(|(_, name, ascii)| (*name, *ascii))
+}
+
 pub(crate) fn try_multi_byte_char(
     cu: &CompilationUnit,
     tokens: &mut Tokens,
@@ -218,14 +352,22 @@ pub(crate) fn try_multi_byte_char(
     s: &str,
 ) -> Option<UcIdx> {
     if s.len() != 1 {
+        // a single confusable character gets a targeted suggestion instead of the generic message,
+        // so paste-from-word-processor mistakes point at the ascii token the user meant
+        let msg = match s.chars().next().and_then(find_confusable) {
+            Some((name, ascii)) if s.chars().count() == 1 => {
+                format!("found `{}` ({}); did you mean `{}`?", s, name, ascii)
+            }
+            _ => format!(
+                "multi-char unicode like `{}` only supported in strings and comments",
+                s
+            ),
+        };
         let new_uc_idx = take_while(cu, uc_idx + 1, |s| s.len() != 1);
         tokens.push(TokenKind::FakeTokenForInvalid, uc_idx, new_uc_idx);
         tokens.push(
             TokenKind::Invalid {
-                msg: format!(
-                    "multi-char unicode like `{}` only supported in strings and comments",
-                    s
-                ),
+                msg,
                 error_fake_token_idx: TokenIdx::new(tokens.len() - 1),
             },
             uc_idx,
@@ -263,8 +405,21 @@ pub(crate) fn try_string(
 
     let new_uc_idx = take_string(cu, uc_idx + 1);
     match new_uc_idx {
-        Ok((new_uc_idx, content)) => {
+        Ok((new_uc_idx, content, escape_errors)) => {
             tokens.push(TokenKind::StringLiteral { content }, uc_idx, new_uc_idx);
+            // each bad escape becomes its own diagnostic, anchored at the escape within the
+            // literal, through the same fake-token channel the lexer uses for other errors
+            for (error_uc_idx, msg) in escape_errors {
+                tokens.push(TokenKind::FakeTokenForInvalid, error_uc_idx, error_uc_idx);
+                tokens.push(
+                    TokenKind::Invalid {
+                        msg,
+                        error_fake_token_idx: TokenIdx::new(tokens.len() - 1),
+                    },
+                    error_uc_idx,
+                    error_uc_idx,
+                );
+            }
             Some(new_uc_idx + 1)
         }
         Err(TakeStringError {
@@ -286,6 +441,61 @@ pub(crate) fn try_string(
     }
 }
 
+// a raw string `r"..."` or `r#"..."#` takes its content verbatim: no escape processing and
+// newlines are allowed. `uc_idx` points at the leading `r`; the body runs until a closing `"`
+// immediately followed by the same number of `#` that opened the literal. running out of input
+// first is an unterminated raw string. a bare `r` not followed by `"`/`#` never reaches here — it
+// stays an ordinary identifier handled by `must_be_name`.
+fn must_be_raw_string(cu: &CompilationUnit, tokens: &mut Tokens, uc_idx: UcIdx) -> UcIdx {
+    let unterminated = |tokens: &mut Tokens, end: UcIdx| -> UcIdx {
+        tokens.push(TokenKind::FakeTokenForInvalid, uc_idx, uc_idx);
+        tokens.push(
+            TokenKind::Invalid {
+                msg: "unterminated raw string literal".to_owned(),
+                error_fake_token_idx: TokenIdx::new(tokens.len() - 1),
+            },
+            uc_idx,
+            end,
+        );
+        UcIdx::new(cu.ucs.len())
+    };
+
+    // count the opening `#` run, then the required opening quote
+    let mut idx = uc_idx + 1;
+    let mut hashes = 0usize;
+    while cu.get_str(idx) == Some("#") {
+        hashes += 1;
+        idx += 1;
+    }
+    if cu.get_str(idx) != Some("\"") {
+        return unterminated(tokens, idx - 1);
+    }
+
+    let content_start = idx + 1;
+    let mut content = String::with_capacity(50);
+    idx = content_start;
+    loop {
+        match cu.get_str(idx) {
+            None => return unterminated(tokens, idx - 1),
+            Some("\"") if raw_string_closes(cu, idx + 1, hashes) => {
+                let end = idx + hashes; // closing quote plus its `#` run
+                tokens.push(TokenKind::StringLiteral { content }, uc_idx, end);
+                return end + 1;
+            }
+            Some(s) => {
+                content.push_str(s);
+                idx += 1;
+            }
+        }
+    }
+}
+
+// true when the `hashes` unicode chars starting at `idx` are all `#`, i.e. the `"` just before
+// `idx` closes a raw string opened with that many `#`
+fn raw_string_closes(cu: &CompilationUnit, idx: UcIdx, hashes: usize) -> bool {
+    (0..hashes).all(|i| cu.get_str(idx + i) == Some("#"))
+}
+
 pub(crate) fn try_multi_byte_tokens(
     cu: &CompilationUnit,
     tokens: &mut Tokens,
@@ -297,15 +507,59 @@ pub(crate) fn try_multi_byte_tokens(
         '1'..='9' => Some(must_be_integer(cu, tokens, uc_idx)),
         ' ' => Some(must_be_spaces(cu, tokens, uc_idx)),
         '#' => Some(must_be_comment(cu, tokens, uc_idx)),
+        '/' => Some(must_be_slash_or_block_comment(cu, tokens, uc_idx)),
+        'r' if matches!(cu.get_str(uc_idx + 1), Some("\"") | Some("#")) => {
+            Some(must_be_raw_string(cu, tokens, uc_idx))
+        }
         'a'..='z' | 'A'..='Z' | '_' => Some(must_be_name(cu, tokens, uc_idx, c)),
+        '-' => Some(must_be_minus_or_arrow(cu, tokens, uc_idx)),
         '>' => Some(must_be_gt_or_gteq(cu, tokens, uc_idx)),
         '<' => Some(must_be_lt_or_lteq(cu, tokens, uc_idx)),
         '=' => Some(must_be_eq_or_assign(cu, tokens, uc_idx)),
+        '*' => Some(must_be_star_or_starstar(cu, tokens, uc_idx)),
         '!' => Some(must_be_not_eq_or_bitwise_not(cu, tokens, uc_idx)),
+        '&' => Some(must_be_logical_and(cu, tokens, uc_idx)),
+        '|' => Some(must_be_logical_or(cu, tokens, uc_idx)),
         _ => None,
     }
 }
 
+fn must_be_logical_and(cu: &CompilationUnit, tokens: &mut Tokens, uc_idx: UcIdx) -> UcIdx {
+    if cu.get_str(uc_idx + 1) == Some("&") {
+        tokens.push(TokenKind::AmpAmp, uc_idx, uc_idx + 1);
+        uc_idx + 2
+    } else {
+        tokens.push(TokenKind::FakeTokenForInvalid, uc_idx, uc_idx);
+        tokens.push(
+            TokenKind::Invalid {
+                msg: "did you mean `&&`? single `&` is not supported".to_owned(),
+                error_fake_token_idx: TokenIdx::new(tokens.len() - 1),
+            },
+            uc_idx,
+            uc_idx,
+        );
+        uc_idx + 1
+    }
+}
+
+fn must_be_logical_or(cu: &CompilationUnit, tokens: &mut Tokens, uc_idx: UcIdx) -> UcIdx {
+    if cu.get_str(uc_idx + 1) == Some("|") {
+        tokens.push(TokenKind::PipePipe, uc_idx, uc_idx + 1);
+        uc_idx + 2
+    } else {
+        tokens.push(TokenKind::FakeTokenForInvalid, uc_idx, uc_idx);
+        tokens.push(
+            TokenKind::Invalid {
+                msg: "did you mean `||`? single `|` is not supported".to_owned(),
+                error_fake_token_idx: TokenIdx::new(tokens.len() - 1),
+            },
+            uc_idx,
+            uc_idx,
+        );
+        uc_idx + 1
+    }
+}
+
 fn must_be_not_eq_or_bitwise_not(
     cu: &CompilationUnit,
     tokens: &mut Tokens,
@@ -320,6 +574,26 @@ fn must_be_not_eq_or_bitwise_not(
     new_uc_idx + 1
 }
 
+fn must_be_star_or_starstar(cu: &CompilationUnit, tokens: &mut Tokens, uc_idx: UcIdx) -> UcIdx {
+    let (new_uc_idx, kind) = if cu.get_str(uc_idx + 1) == Some("*") {
+        (uc_idx + 1, TokenKind::StarStar)
+    } else {
+        (uc_idx, TokenKind::Star)
+    };
+    tokens.push(kind, uc_idx, new_uc_idx);
+    new_uc_idx + 1
+}
+
+fn must_be_minus_or_arrow(cu: &CompilationUnit, tokens: &mut Tokens, uc_idx: UcIdx) -> UcIdx {
+    let (new_uc_idx, kind) = if cu.get_str(uc_idx + 1) == Some(">") {
+        (uc_idx + 1, TokenKind::Arrow)
+    } else {
+        (uc_idx, TokenKind::Minus)
+    };
+    tokens.push(kind, uc_idx, new_uc_idx);
+    new_uc_idx + 1
+}
+
 fn must_be_gt_or_gteq(cu: &CompilationUnit, tokens: &mut Tokens, uc_idx: UcIdx) -> UcIdx {
     let (new_uc_idx, kind) = if cu.get_str(uc_idx + 1) == Some("=") {
         (uc_idx + 1, TokenKind::Ge)
@@ -341,10 +615,10 @@ fn must_be_lt_or_lteq(cu: &CompilationUnit, tokens: &mut Tokens, uc_idx: UcIdx)
 }
 
 fn must_be_eq_or_assign(cu: &CompilationUnit, tokens: &mut Tokens, uc_idx: UcIdx) -> UcIdx {
-    let (new_uc_idx, kind) = if cu.get_str(uc_idx + 1) == Some("=") {
-        (uc_idx + 1, TokenKind::EqEq)
-    } else {
-        (uc_idx, TokenKind::Eq)
+    let (new_uc_idx, kind) = match cu.get_str(uc_idx + 1) {
+        Some("=") => (uc_idx + 1, TokenKind::EqEq),
+        Some(">") => (uc_idx + 1, TokenKind::FatArrow),
+        _ => (uc_idx, TokenKind::Eq),
     };
     tokens.push(kind, uc_idx, new_uc_idx);
     new_uc_idx + 1
@@ -356,25 +630,108 @@ pub(crate) fn must_be_comment(cu: &CompilationUnit, tokens: &mut Tokens, uc_idx:
     new_uc_idx + 1
 }
 
+// `/` either opens a nested block comment `/* ... */` or stands alone as `Slash`. block comments
+// nest: each inner `/*` raises a depth counter and each `*/` lowers it, so the comment ends only
+// when depth returns to 0. an input that runs out with depth still open is an unterminated comment.
+fn must_be_slash_or_block_comment(cu: &CompilationUnit, tokens: &mut Tokens, uc_idx: UcIdx) -> UcIdx {
+    if cu.get_str(uc_idx + 1) != Some("*") {
+        tokens.push(TokenKind::Slash, uc_idx, uc_idx);
+        return uc_idx + 1;
+    }
+
+    let mut depth = 1usize;
+    let mut idx = uc_idx + 2;
+    while depth > 0 {
+        match cu.get_str(idx) {
+            Some("/") if cu.get_str(idx + 1) == Some("*") => {
+                depth += 1;
+                idx += 2;
+            }
+            Some("*") if cu.get_str(idx + 1) == Some("/") => {
+                depth -= 1;
+                idx += 2;
+            }
+            Some(_) => idx += 1,
+            None => {
+                // ran off the end with the comment still open; anchor the error at the opening `/*`
+                tokens.push(TokenKind::FakeTokenForInvalid, uc_idx, uc_idx + 1);
+                tokens.push(
+                    TokenKind::Invalid {
+                        msg: "unterminated block comment".to_owned(),
+                        error_fake_token_idx: TokenIdx::new(tokens.len() - 1),
+                    },
+                    uc_idx,
+                    uc_idx + 1,
+                );
+                return idx;
+            }
+        }
+    }
+
+    tokens.push(TokenKind::Comment, uc_idx, idx - 1);
+    idx
+}
+
+// an integer literal may carry an explicit type suffix like `i64` or `u8`; on a match this
+// returns the inclusive end of the suffix, otherwise `None` so the letters are left to be lexed
+// on their own (e.g. `3x`)
+fn scan_int_suffix(cu: &CompilationUnit, start: UcIdx) -> Option<UcIdx> {
+    match cu.get_str(start) {
+        Some("i") | Some("u") => {}
+        _ => return None,
+    }
+    let mut idx = start + 1;
+    while cu.get_str(idx).is_some_and(uc_is_ascii_digit) {
+        idx += 1;
+    }
+    if idx == start + 1 {
+        return None;
+    }
+    let suffix = cu.get_str((start, idx - 1))?;
+    if !matches!(
+        suffix,
+        "i8" | "i16" | "i32" | "i64" | "u8" | "u16" | "u32" | "u64"
+    ) {
+        return None;
+    }
+    // reject things like `3u8x` where more identifier characters follow the suffix
+    match cu.get_str(idx) {
+        Some(s)
+            if s.len() == 1
+                && (s.chars().next().unwrap().is_ascii_alphanumeric() || s == "_") =>
+        {
+            None
+        }
+        _ => Some(idx - 1),
+    }
+}
+
 fn must_be_single_zero(cu: &CompilationUnit, tokens: &mut Tokens, uc_idx: UcIdx) -> UcIdx {
-    let new_uc_idx = take_while(cu, uc_idx + 1, uc_is_ascii_digit);
-    let kind = if new_uc_idx == uc_idx {
-        TokenKind::I64
+    let digits_end = take_while(cu, uc_idx + 1, uc_is_ascii_digit);
+    if digits_end == uc_idx {
+        // single `0`, possibly with a type suffix
+        let end = scan_int_suffix(cu, uc_idx + 1).unwrap_or(uc_idx);
+        tokens.push(TokenKind::I64, uc_idx, end);
+        end + 1
     } else {
-        tokens.push(TokenKind::FakeTokenForInvalid, uc_idx, new_uc_idx);
-        TokenKind::Invalid {
-            msg: "leading zero is not allowed".to_owned(),
-            error_fake_token_idx: TokenIdx::new(tokens.len() - 1),
-        }
-    };
-    tokens.push(kind, uc_idx, new_uc_idx);
-    new_uc_idx + 1
+        tokens.push(TokenKind::FakeTokenForInvalid, uc_idx, digits_end);
+        tokens.push(
+            TokenKind::Invalid {
+                msg: "leading zero is not allowed".to_owned(),
+                error_fake_token_idx: TokenIdx::new(tokens.len() - 1),
+            },
+            uc_idx,
+            digits_end,
+        );
+        digits_end + 1
+    }
 }
 
 fn must_be_integer(cu: &CompilationUnit, tokens: &mut Tokens, uc_idx: UcIdx) -> UcIdx {
-    let new_uc_idx = take_while(cu, uc_idx + 1, uc_is_ascii_digit);
-    tokens.push(TokenKind::I64, uc_idx, new_uc_idx);
-    new_uc_idx + 1
+    let digits_end = take_while(cu, uc_idx + 1, uc_is_ascii_digit);
+    let end = scan_int_suffix(cu, digits_end + 1).unwrap_or(digits_end);
+    tokens.push(TokenKind::I64, uc_idx, end);
+    end + 1
 }
 
 fn must_be_spaces(cu: &CompilationUnit, tokens: &mut Tokens, uc_idx: UcIdx) -> UcIdx {
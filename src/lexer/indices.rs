@@ -4,8 +4,6 @@
 pub(super) struct ByteIdx(usize);
 
 impl ByteIdx {
-    pub(super) const MAX: Self = Self(usize::MAX);
-
     pub(super) fn new(i: usize) -> Self {
         Self(i)
     }
@@ -14,7 +12,7 @@ impl ByteIdx {
     }
 }
 
-impl std::ops::Add<usize> for ByteIdx {
+impl core::ops::Add<usize> for ByteIdx {
     type Output = Self;
 
     fn add(self, rhs: usize) -> Self::Output {
@@ -68,7 +66,7 @@ impl UcIdx {
     }
 }
 
-impl std::ops::Add<usize> for UcIdx {
+impl core::ops::Add<usize> for UcIdx {
     type Output = Self;
 
     fn add(self, rhs: usize) -> Self::Output {
@@ -76,13 +74,13 @@ impl std::ops::Add<usize> for UcIdx {
     }
 }
 
-impl std::ops::AddAssign<usize> for UcIdx {
+impl core::ops::AddAssign<usize> for UcIdx {
     fn add_assign(&mut self, rhs: usize) {
         *self = *self + rhs;
     }
 }
 
-impl std::ops::Sub<usize> for UcIdx {
+impl core::ops::Sub<usize> for UcIdx {
     type Output = Self;
 
     fn sub(self, rhs: usize) -> Self::Output {
@@ -90,13 +88,13 @@ impl std::ops::Sub<usize> for UcIdx {
     }
 }
 
-impl std::ops::SubAssign<usize> for UcIdx {
+impl core::ops::SubAssign<usize> for UcIdx {
     fn sub_assign(&mut self, rhs: usize) {
         *self = *self - rhs;
     }
 }
 
-impl std::ops::Sub<UcIdx> for UcIdx {
+impl core::ops::Sub<UcIdx> for UcIdx {
     type Output = UcSpan;
 
     fn sub(self, rhs: UcIdx) -> Self::Output {
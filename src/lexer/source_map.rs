@@ -0,0 +1,102 @@
+// the map is a forward-looking subsystem: some `add_*`/`get` entry points are wired up as
+// multi-file support lands, so not every one has a caller yet
+#![allow(dead_code)]
+
+use super::CompilationUnit;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Identifies a [`CompilationUnit`] owned by a [`SourceMap`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnitId(usize);
+
+impl UnitId {
+    fn new(i: usize) -> Self {
+        Self(i)
+    }
+    pub fn get(&self) -> usize {
+        self.0
+    }
+}
+
+#[derive(Debug)]
+struct MappedUnit {
+    cu: CompilationUnit,
+    // half-open global byte range `[global_start, global_end)` this unit occupies in the map
+    global_start: usize,
+    global_end: usize,
+}
+
+impl MappedUnit {
+    fn new(cu: CompilationUnit, global_start: usize) -> Self {
+        let global_end = global_start + cu.bytes_len();
+        Self {
+            cu,
+            global_start,
+            global_end,
+        }
+    }
+}
+
+/// Owns many [`CompilationUnit`]s and lays them out end to end in a single global byte space, so a
+/// span can be reported with an absolute origin regardless of which unit it came from.
+///
+/// Each unit is handed a non-overlapping global range as it is added, mirroring the source-map
+/// idea in proc-macro2's fallback lexer.
+#[derive(Debug)]
+pub struct SourceMap {
+    units: Vec<MappedUnit>,
+    next_global: usize,
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        Self {
+            units: Vec::new(),
+            next_global: 0,
+        }
+    }
+
+    /// Reads a file, appends it to the map, and returns its [`UnitId`].
+    #[cfg(feature = "std")]
+    pub fn add_file<P>(&mut self, path: P) -> Result<UnitId, String>
+    where
+        P: AsRef<std::path::Path>,
+    {
+        let cu = CompilationUnit::from_file(path)?;
+        Ok(self.push(cu))
+    }
+
+    /// Appends an in-memory unit to the map and returns its [`UnitId`].
+    pub fn add_string(&mut self, mark: &str, input: &str) -> UnitId {
+        let cu = CompilationUnit::from_string(mark, input);
+        self.push(cu)
+    }
+
+    fn push(&mut self, cu: CompilationUnit) -> UnitId {
+        let unit = MappedUnit::new(cu, self.next_global);
+        self.next_global = unit.global_end;
+        self.units.push(unit);
+        UnitId::new(self.units.len() - 1)
+    }
+
+    pub fn get(&self, id: UnitId) -> &CompilationUnit {
+        &self
+            .units
+            .get(id.get())
+            .unwrap_or_else(|| panic!("BUG: no unit {:?} in source map", id))
+            .cu
+    }
+
+    /// The absolute origin (file path or mark) of a unit, for diagnostics.
+    pub fn origin_of(&self, id: UnitId) -> String {
+        self.get(id).get_origin()
+    }
+}
+
+impl Default for SourceMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
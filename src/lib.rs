@@ -1,5 +1,16 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+// the lexer front-end is `no_std` + `alloc` clean so it can be embedded in constrained or
+// bytecode-VM contexts; the tree/printer/parser layers still lean on `std` (hashing, `colored`
+// output) and are only built with the default `std` feature on.
+#[cfg(feature = "std")]
 pub mod ast;
+#[cfg(feature = "std")]
+pub mod cst;
 pub mod lexer;
+#[cfg(feature = "std")]
 pub mod parser;
 
 #[doc = include_str!("../README.md")]
@@ -21,28 +21,17 @@ impl From<SingleParseError> for ParseError {
 }
 
 trait ParseResultExt {
-    fn is_finished(&self) -> bool;
     fn new_node(node: ast::AstNode, next_token_idx: lexer::TokenIdx) -> Self;
     fn new_finished() -> Self;
-    fn new_single_error(error: SingleParseError) -> Self;
-    fn new_error_lex_error(errors: Vec<(lexer::TokenIdx, String)>) -> Self;
     fn new_error_unexpected_token(
         msg: impl Into<String>,
         start_token_idx: lexer::TokenIdx,
         inclusive_end_token_idx: lexer::TokenIdx,
     ) -> Self;
     fn new_error_unexpected_eof(msg: impl Into<String>, start_token_idx: lexer::TokenIdx) -> Self;
-    fn new_error_mismatched_paren(
-        lparen: lexer::TokenIdx,
-        start_token_idx: lexer::TokenIdx,
-    ) -> Self;
-    fn add_upper_context_to_error(ctx_msg: impl Into<String>, error: ParseError) -> Self;
 }
 
 impl ParseResultExt for ParseResult {
-    fn is_finished(&self) -> bool {
-        matches!(self, Ok(HappyPath::Finished))
-    }
     fn new_node(node: ast::AstNode, next_token_idx: lexer::TokenIdx) -> Self {
         Ok(HappyPath::Node {
             node,
@@ -52,14 +41,6 @@ impl ParseResultExt for ParseResult {
     fn new_finished() -> Self {
         Ok(HappyPath::Finished)
     }
-    fn new_single_error(error: SingleParseError) -> Self {
-        Err(ParseError::new_single_error(error))
-    }
-    fn new_error_lex_error(errors: Vec<(lexer::TokenIdx, String)>) -> Self {
-        Err(ParseError::new_single_error(SingleParseError::LexErrors(
-            errors,
-        )))
-    }
     fn new_error_unexpected_token(
         msg: impl Into<String>,
         start_token_idx: lexer::TokenIdx,
@@ -81,20 +62,6 @@ impl ParseResultExt for ParseResult {
             },
         ))
     }
-    fn new_error_mismatched_paren(
-        lparen: lexer::TokenIdx,
-        start_token_idx: lexer::TokenIdx,
-    ) -> Self {
-        Err(ParseError::new_single_error(
-            SingleParseError::MismatchedParentheses {
-                lparen,
-                error_token_idx: start_token_idx,
-            },
-        ))
-    }
-    fn add_upper_context_to_error(ctx_msg: impl Into<String>, error: ParseError) -> Self {
-        Err(error.add_error_context(ctx_msg))
-    }
 }
 
 #[derive(Debug, Clone)]
@@ -155,6 +122,41 @@ impl ParseError {
             }
         }
     }
+
+    // newline-delimited JSON: one object per `SingleParseError`. `ErrorWithContext` folds its
+    // trailing context entries into the lead record's `children`, while `MutilParseError` simply
+    // concatenates the records of its members.
+    fn get_json<'cu>(&self, ast: &'cu ast::Ast<'cu, ParseError>) -> String {
+        match self {
+            Self::Empty => panic!("BUG: cannot get json from empty error"),
+            Self::SingleParseError(error) => error.get_json(ast, &[]),
+            Self::ErrorWithContext(errors) => {
+                let (lead, notes) = errors
+                    .split_first()
+                    .expect("BUG: ErrorWithContext without a lead error");
+                let children = notes.iter().filter_map(ParseError::context_note).collect::<Vec<_>>();
+                match lead {
+                    Self::SingleParseError(error) => error.get_json(ast, &children),
+                    other => other.get_json(ast),
+                }
+            }
+            Self::MutilParseError(errors) => errors
+                .iter()
+                .map(|e| e.get_json(ast))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        }
+    }
+
+    // the `{"message": ...}` child note contributed by a `Context` entry; anything else has no note
+    fn context_note(&self) -> Option<String> {
+        match self {
+            Self::SingleParseError(SingleParseError::Context { msg }) => {
+                Some(format!("{{\"message\":\"{}\"}}", json_escape(msg)))
+            }
+            _ => None,
+        }
+    }
 }
 
 impl ast::AstError for ParseError {
@@ -166,6 +168,9 @@ impl ast::AstError for ParseError {
     fn get_string<'cu>(&self, ast: &'cu ast::Ast<'cu, Self>) -> String {
         self.get_string(ast)
     }
+    fn get_json<'cu>(&self, ast: &'cu ast::Ast<'cu, Self>) -> String {
+        self.get_json(ast)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -196,15 +201,24 @@ impl SingleParseError {
     fn get_context_msg(&self, msg: &str) -> String {
         format!("context: {}\n", msg).blue().bold().to_string()
     }
-    fn get_error_msg<'cu>(
+    // renders the `file:line:col: error: msg` header above the caret diagnostic; the location is
+    // taken from the diagnostic's primary error span so it reads like a conventional compiler
+    // position. `position` is `None` only when the span could not be resolved, and the header then
+    // falls back to the bare filename.
+    fn get_error_msg(
         &self,
-        ast: &'cu ast::Ast<'cu, ParseError>,
+        ast: &ast::Ast<ParseError>,
         msg: &str,
+        position: Option<(usize, usize)>,
         diag: &str,
     ) -> String {
+        let location = match position {
+            Some((line, col)) => format!("{}:{}:{}", ast.get_input_origin(), line, col),
+            None => ast.get_input_origin(),
+        };
         format!(
-            "{filename}: {cate}: {msg}\n{diag}",
-            filename = ast.get_input_origin().bold(),
+            "{location}: {cate}: {msg}\n{diag}",
+            location = location.bold(),
             cate = "error".red().bold(),
             msg = msg.red().bold(),
             diag = diag,
@@ -213,57 +227,223 @@ impl SingleParseError {
     fn get_string<'cu>(&self, ast: &'cu ast::Ast<'cu, ParseError>) -> String {
         match self {
             Self::Context { msg } => self.get_context_msg(msg),
-            Self::UnexpectedEof { msg, ctx_token_idx } => self.get_error_msg(
-                ast,
+            Self::UnexpectedEof { msg, ctx_token_idx } => {
+                let diag =
+                    ast.get_diag_with_ctx_token(*ctx_token_idx, lexer::SpanLabels::none());
+                self.get_error_msg(
+                    ast,
+                    &format!("unexpected end of file, {}", msg),
+                    diag.primary_position(),
+                    &diag.to_string(),
+                )
+            }
+            Self::IntegerOverflow { token: token_idx } => {
+                let diag = ast.get_diag_with_ctx_token(*token_idx, lexer::SpanLabels::none());
+                self.get_error_msg(
+                    ast,
+                    &format!(
+                        "integer overflow `{}`",
+                        ast.get_string_unchecked(*token_idx)
+                    ),
+                    diag.primary_position(),
+                    &diag.to_string(),
+                )
+            }
+            Self::MismatchedParentheses {
+                lparen,
+                error_token_idx,
+            } => {
+                // one diagnostic with two labelled regions: the opening paren as secondary context
+                // and the offending token as the primary error
+                let diag = ast.get_diag_with_ctx_and_error_tokens(
+                    *lparen,
+                    *error_token_idx,
+                    lexer::SpanLabels::ctx_and_error(
+                        format!("unclosed `{}` opened here", ast.get_string_unchecked(*lparen)),
+                        "expected `)` but found this",
+                    ),
+                );
+                self.get_error_msg(
+                    ast,
+                    &format!(
+                        "mismatched parentheses `{}`",
+                        ast.get_string_unchecked(*lparen)
+                    ),
+                    diag.primary_position(),
+                    &diag.to_string(),
+                )
+            }
+            Self::UnexpectedToken {
+                msg,
+                ctx_start_token_idx: start_token_idx,
+                error_token_idx,
+            } => {
+                let diag = ast.get_diag_with_ctx_and_error_tokens(
+                    *start_token_idx,
+                    *error_token_idx,
+                    lexer::SpanLabels::ctx_and_error("while parsing this", "unexpected token"),
+                );
+                self.get_error_msg(ast, msg, diag.primary_position(), &diag.to_string())
+            }
+            Self::LexErrors(errors) => {
+                let mut result = String::with_capacity(errors.len() * 80);
+                for (error_token_idx, msg) in errors {
+                    let diag = ast
+                        .get_diag_with_error_token(*error_token_idx, lexer::SpanLabels::none());
+                    let formatted =
+                        self.get_error_msg(ast, msg, diag.primary_position(), &diag.to_string());
+                    result.push_str(&formatted);
+                }
+                result
+            }
+        }
+    }
+
+    // builds this error's JSON record(s). `children` are context notes threaded down from an
+    // enclosing `ErrorWithContext`. `LexErrors` expands to one record per lexer error, joined by
+    // newlines so the output stays one-object-per-line.
+    fn get_json(&self, ast: &ast::Ast<ParseError>, children: &[String]) -> String {
+        let file = json_escape(&ast.get_input_origin());
+        match self {
+            Self::Context { msg } => json_record(&file, "note", msg, &[], children),
+            Self::UnexpectedEof { msg, ctx_token_idx } => json_record(
+                &file,
+                "error",
                 &format!("unexpected end of file, {}", msg),
-                &ast.get_diag_with_ctx_token(*ctx_token_idx),
+                &[JsonSpan::of(ast, *ctx_token_idx, None)],
+                children,
             ),
-            Self::IntegerOverflow { token: token_idx } => self.get_error_msg(
-                ast,
-                &format!(
-                    "integer overflow `{}`",
-                    ast.get_string_unchecked(*token_idx)
-                ),
-                &ast.get_diag_with_ctx_token(*token_idx),
+            Self::IntegerOverflow { token } => json_record(
+                &file,
+                "error",
+                &format!("integer overflow `{}`", ast.get_string_unchecked(*token)),
+                &[JsonSpan::of(ast, *token, None)],
+                children,
             ),
             Self::MismatchedParentheses {
                 lparen,
                 error_token_idx,
-            } => self.get_error_msg(
-                ast,
+            } => json_record(
+                &file,
+                "error",
                 &format!(
                     "mismatched parentheses `{}`",
                     ast.get_string_unchecked(*lparen)
                 ),
                 &[
-                    ast.get_diag_with_ctx_token(*lparen),
-                    ast.get_diag_with_ctx_token(*error_token_idx),
-                ]
-                .join(""),
+                    JsonSpan::of(ast, *error_token_idx, Some("expected `)` but found this")),
+                    JsonSpan::of(ast, *lparen, Some("unclosed `(` opened here")),
+                ],
+                children,
             ),
             Self::UnexpectedToken {
                 msg,
-                ctx_start_token_idx: start_token_idx,
+                ctx_start_token_idx,
                 error_token_idx,
-            } => self.get_error_msg(
-                ast,
+            } => json_record(
+                &file,
+                "error",
                 msg,
-                &ast.get_diag_with_ctx_and_error_tokens(*start_token_idx, *error_token_idx),
+                &[
+                    JsonSpan::of(ast, *error_token_idx, Some("unexpected token")),
+                    JsonSpan::of(ast, *ctx_start_token_idx, Some("while parsing this")),
+                ],
+                children,
             ),
-            Self::LexErrors(errors) => {
-                let mut result = String::with_capacity(errors.len() * 80);
-                for (error_token_idx, msg) in errors {
-                    let formatted = self.get_error_msg(
-                        ast,
+            Self::LexErrors(errors) => errors
+                .iter()
+                .map(|(error_token_idx, msg)| {
+                    json_record(
+                        &file,
+                        "error",
                         msg,
-                        &ast.get_diag_with_error_token(*error_token_idx),
-                    );
-                    result.push_str(&formatted);
-                }
-                result
-            }
+                        &[JsonSpan::of(ast, *error_token_idx, None)],
+                        children,
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+        }
+    }
+}
+
+// escapes a string for embedding in a JSON double-quoted literal
+fn json_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+// a single `spans` entry: the byte range plus resolved line/column of one token, with an optional
+// label describing its role in the diagnostic
+struct JsonSpan {
+    byte_start: usize,
+    byte_end: usize,
+    line: usize,
+    col: usize,
+    label: Option<&'static str>,
+}
+
+impl JsonSpan {
+    fn of(
+        ast: &ast::Ast<ParseError>,
+        token_idx: lexer::TokenIdx,
+        label: Option<&'static str>,
+    ) -> Self {
+        let (byte_start, byte_end) = ast.token_byte_range(token_idx);
+        let (line, col) = ast.token_line_col(token_idx);
+        Self {
+            byte_start,
+            byte_end,
+            line,
+            col,
+            label,
         }
     }
+
+    fn render(&self, file: &str) -> String {
+        let label = match self.label {
+            Some(l) => format!("\"{}\"", json_escape(l)),
+            None => String::from("null"),
+        };
+        format!(
+            "{{\"file\":\"{file}\",\"byte_start\":{bs},\"byte_end\":{be},\"line\":{line},\"col\":{col},\"label\":{label}}}",
+            bs = self.byte_start,
+            be = self.byte_end,
+            line = self.line,
+            col = self.col,
+        )
+    }
+}
+
+// assembles one diagnostic record from its parts; `spans` and `children` are already-rendered JSON
+fn json_record(
+    file: &str,
+    severity: &str,
+    message: &str,
+    spans: &[JsonSpan],
+    children: &[String],
+) -> String {
+    let spans = spans
+        .iter()
+        .map(|s| s.render(file))
+        .collect::<Vec<_>>()
+        .join(",");
+    format!(
+        "{{\"severity\":\"{severity}\",\"message\":\"{msg}\",\"spans\":[{spans}],\"children\":[{children}]}}",
+        msg = json_escape(message),
+        children = children.join(","),
+    )
 }
 
 pub fn parse(cu: &lexer::CompilationUnit) -> ast::Ast<ParseError> {
@@ -336,6 +516,305 @@ mod test_parser {
         );
     }
 
+    #[test]
+    fn test_sexp_dump() {
+        for (s, expected) in [
+            ("1 + 2 * 3;", "(module (+ (i64 1) (* (i64 2) (i64 3))))"),
+            ("let x = -3;", "(module (def (id x) (neg (i64 3))))"),
+            (
+                "if x == 2 { y; } else { z; }",
+                "(module (if (== (id x) (i64 2)) (block (id y)) (block (id z))))",
+            ),
+        ] {
+            let cu = lexer::CompilationUnit::from_string("stdin", s);
+            let ast = parse(&cu);
+            assert!(ast.get_error().is_none(), "ast: {}", ast);
+            let result = ast.accept(&mut ast::AstSexpDumper::new(&ast));
+            assert_eq!(result, expected, "input: {}, ast: {}", s, ast);
+        }
+    }
+
+    #[test]
+    fn test_prefix_operators() {
+        for (s, expected) in [
+            ("-x;", "(module (neg (id x)))"),
+            ("--x;", "(module (neg (neg (id x))))"),
+            ("!cond;", "(module (not (id cond)))"),
+            ("!!x;", "(module (not (not (id x))))"),
+            ("-(a + b);", "(module (neg (group (+ (id a) (id b)))))"),
+            ("-a * b;", "(module (* (neg (id a)) (id b)))"),
+            ("-a + b;", "(module (+ (neg (id a)) (id b)))"),
+        ] {
+            let cu = lexer::CompilationUnit::from_string("stdin", s);
+            let ast = parse(&cu);
+            assert!(ast.get_error().is_none(), "ast: {}", ast);
+            let result = ast.accept(&mut ast::AstSexpDumper::new(&ast));
+            assert_eq!(result, expected, "input: {}, ast: {}", s, ast);
+        }
+    }
+
+    #[test]
+    fn test_assignment_and_exponent() {
+        for (s, expected) in [
+            ("x = 1;", "(module (= (id x) (i64 1)))"),
+            ("a = b = c;", "(module (= (id a) (= (id b) (id c))))"),
+            (
+                "2 ** 3 ** 2;",
+                "(module (** (i64 2) (** (i64 3) (i64 2))))",
+            ),
+            ("2 ** 3 * 4;", "(module (* (** (i64 2) (i64 3)) (i64 4)))"),
+        ] {
+            let cu = lexer::CompilationUnit::from_string("stdin", s);
+            let ast = parse(&cu);
+            assert!(ast.get_error().is_none(), "ast: {}", ast);
+            let result = ast.accept(&mut ast::AstSexpDumper::new(&ast));
+            assert_eq!(result, expected, "input: {}, ast: {}", s, ast);
+        }
+    }
+
+    #[test]
+    fn test_call_and_index() {
+        for (s, expected) in [
+            ("f();", "(module (call (id f)))"),
+            ("f(a);", "(module (call (id f) (id a)))"),
+            (
+                "f(a, b + 1);",
+                "(module (call (id f) (id a) (+ (id b) (i64 1))))",
+            ),
+            ("arr[0];", "(module (index (id arr) (i64 0)))"),
+            (
+                "m[i][j];",
+                "(module (index (index (id m) (id i)) (id j)))",
+            ),
+            (
+                "f(a)(b);",
+                "(module (call (call (id f) (id a)) (id b)))",
+            ),
+            (
+                "g(x)[1];",
+                "(module (index (call (id g) (id x)) (i64 1)))",
+            ),
+            (
+                "-f(x);",
+                "(module (neg (call (id f) (id x))))",
+            ),
+        ] {
+            let cu = lexer::CompilationUnit::from_string("stdin", s);
+            let ast = parse(&cu);
+            assert!(ast.get_error().is_none(), "ast: {}", ast);
+            let result = ast.accept(&mut ast::AstSexpDumper::new(&ast));
+            assert_eq!(result, expected, "input: {}, ast: {}", s, ast);
+        }
+    }
+
+    #[test]
+    fn test_type_annotations() {
+        for (s, expected) in [
+            ("let x: List<T> = a;", "let x: List<T> = a;\n"),
+            ("let x: Map<K, V> = a;", "let x: Map<K, V> = a;\n"),
+            ("let x: (A, B, C) = a;", "let x: (A, B, C) = a;\n"),
+            ("let x: fn(A, B) -> C = a;", "let x: fn(A, B) -> C = a;\n"),
+            (
+                "let x: Map<K, List<V>> = a;",
+                "let x: Map<K, List<V>> = a;\n",
+            ),
+            (
+                "let x: fn(A) -> fn(B) -> C = a;",
+                "let x: fn(A) -> fn(B) -> C = a;\n",
+            ),
+        ] {
+            let cu = lexer::CompilationUnit::from_string("stdin", s);
+            let ast = parse(&cu);
+            assert!(ast.get_error().is_none(), "ast: {}", ast);
+            let result = ast.accept(&mut ast::AstPrinter::new(&ast));
+            assert_eq!(result, expected, "input: {}, ast: {}", s, ast);
+        }
+    }
+
+    #[test]
+    fn test_match_expression() {
+        for (s, expected) in [
+            (
+                "match x { 1 => a, _ => b };",
+                "(module (match (id x) (arm (i64 1) (id a)) (arm (pat _) (id b))))",
+            ),
+            (
+                "match v { x => x };",
+                "(module (match (id v) (arm (bind x) (id x))))",
+            ),
+            (
+                "match n { 0 => \"zero\", _ => \"other\" };",
+                "(module (match (id n) (arm (i64 0) (str zero)) (arm (pat _) (str other))))",
+            ),
+            (
+                "match p { (a, b) => { a; }, _ => c };",
+                "(module (match (id p) (arm (tuple-pat (bind a) (bind b)) (block (id a))) (arm (pat _) (id c))))",
+            ),
+        ] {
+            let cu = lexer::CompilationUnit::from_string("stdin", s);
+            let ast = parse(&cu);
+            assert!(ast.get_error().is_none(), "ast: {}", ast);
+            let result = ast.accept(&mut ast::AstSexpDumper::new(&ast));
+            assert_eq!(result, expected, "input: {}, ast: {}", s, ast);
+        }
+    }
+
+    #[test]
+    fn test_if_let_and_let_chains() {
+        for (s, expected) in [
+            (
+                "if let x = a { b };",
+                "(module (if (let (bind x) (id a)) (block (tail (id b)))))",
+            ),
+            (
+                "if let x = a && c { d };",
+                "(module (if (&& (let (bind x) (id a)) (id c)) (block (tail (id d)))))",
+            ),
+            (
+                "if let x = a && let y = b { c };",
+                "(module (if (&& (let (bind x) (id a)) (let (bind y) (id b))) (block (tail (id c)))))",
+            ),
+            (
+                "while let v = next { v };",
+                "(module (while (let (bind v) (id next)) (block (tail (id v)))))",
+            ),
+            (
+                "if let (a, b) = p { a };",
+                "(module (if (let (tuple-pat (bind a) (bind b)) (id p)) (block (tail (id a)))))",
+            ),
+        ] {
+            let cu = lexer::CompilationUnit::from_string("stdin", s);
+            let ast = parse(&cu);
+            assert!(ast.get_error().is_none(), "ast: {}", ast);
+            let result = ast.accept(&mut ast::AstSexpDumper::new(&ast));
+            assert_eq!(result, expected, "input: {}, ast: {}", s, ast);
+        }
+    }
+
+    #[test]
+    fn test_let_expression_rejected_outside_conditions() {
+        // `let` is only a condition term: in a bare expression, a subexpression, or joined to a
+        // let chain with `||`, it must be an error
+        for s in [
+            "(let x = 1);",
+            "1 + let x = 2;",
+            "if a || let x = b { c };",
+        ] {
+            let cu = lexer::CompilationUnit::from_string("stdin", s);
+            let ast = parse(&cu);
+            assert!(ast.get_error().is_some(), "input: {}, ast: {}", s, ast);
+        }
+    }
+
+    #[test]
+    fn test_error_recovery_reports_many() {
+        // two broken statements in one file both survive as `Expr::Error` holes, and both
+        // diagnostics are reported from a single parse
+        let cu = lexer::CompilationUnit::from_string("stdin", ") + 1; ) + 2;");
+        let ast = parse(&cu);
+        assert!(ast.get_error().is_some(), "ast: {}", ast);
+        let result = ast.accept(&mut ast::AstSexpDumper::new(&ast));
+        assert_eq!(result, "(module (error) (error))", "ast: {}", ast);
+    }
+
+    #[test]
+    fn test_json_diagnostics() {
+        // an unexpected-token error serialises to a single record with a labelled primary span
+        let cu = lexer::CompilationUnit::from_string("stdin", ") + 1;");
+        let ast = parse(&cu);
+        let json = ast.get_diagnostics_json().expect("expected a diagnostic");
+        // one object per line
+        assert_eq!(json.lines().count(), 1, "json: {}", json);
+        assert!(json.contains("\"severity\":\"error\""), "json: {}", json);
+        assert!(json.contains("\"file\":\"stdin\""), "json: {}", json);
+        assert!(json.contains("\"label\":\"unexpected token\""), "json: {}", json);
+        assert!(json.contains("\"line\":1"), "json: {}", json);
+        assert!(json.contains("\"spans\":["), "json: {}", json);
+    }
+
+    #[test]
+    fn test_else_recovery() {
+        // a brace-less `else` body and a chained `else if` missing its `if` both recover to a
+        // usable `If` node while still reporting the problem
+        for (s, expected) in [
+            (
+                "if c { a } else foo;",
+                "(module (if (id c) (block (tail (id a))) (id foo)))",
+            ),
+            (
+                "if c { a } else (x) { b };",
+                "(module (if (id c) (block (tail (id a))) (if (group (id x)) (block (tail (id b))))))",
+            ),
+        ] {
+            let cu = lexer::CompilationUnit::from_string("stdin", s);
+            let ast = parse(&cu);
+            assert!(ast.get_error().is_some(), "input: {}, ast: {}", s, ast);
+            let result = ast.accept(&mut ast::AstSexpDumper::new(&ast));
+            assert_eq!(result, expected, "input: {}, ast: {}", s, ast);
+        }
+
+        // genuine garbage after `else` still stops with a plain error
+        let cu = lexer::CompilationUnit::from_string("stdin", "if c { a } else ;");
+        let ast = parse(&cu);
+        assert!(ast.get_error().is_some(), "ast: {}", ast);
+    }
+
+    #[test]
+    fn test_block_tail_expression() {
+        for (s, expected) in [
+            (
+                "{ let x = 1; x + 1 };",
+                "(module (block (def (id x) (i64 1)) (tail (+ (id x) (i64 1)))))",
+            ),
+            (
+                "if c { a } else { b };",
+                "(module (if (id c) (block (tail (id a))) (block (tail (id b)))))",
+            ),
+            (
+                "{ 1; 2 };",
+                "(module (block (i64 1) (tail (i64 2))))",
+            ),
+        ] {
+            let cu = lexer::CompilationUnit::from_string("stdin", s);
+            let ast = parse(&cu);
+            assert!(ast.get_error().is_none(), "ast: {}", ast);
+            let result = ast.accept(&mut ast::AstSexpDumper::new(&ast));
+            assert_eq!(result, expected, "input: {}, ast: {}", s, ast);
+        }
+    }
+
+    #[test]
+    fn test_loops_and_break_continue() {
+        for (s, expected) in [
+            (
+                "while x < 10 { x = x + 1; }",
+                "(module (while (< (id x) (i64 10)) (block (= (id x) (+ (id x) (i64 1))))))",
+            ),
+            (
+                "loop { break; }",
+                "(module (loop (block (break))))",
+            ),
+            (
+                "loop { break x; }",
+                "(module (loop (block (break (id x)))))",
+            ),
+            (
+                "while cond { continue; }",
+                "(module (while (id cond) (block (continue))))",
+            ),
+            (
+                "loop { break 1 + 2; }",
+                "(module (loop (block (break (+ (i64 1) (i64 2))))))",
+            ),
+        ] {
+            let cu = lexer::CompilationUnit::from_string("stdin", s);
+            let ast = parse(&cu);
+            assert!(ast.get_error().is_none(), "ast: {}", ast);
+            let result = ast.accept(&mut ast::AstSexpDumper::new(&ast));
+            assert_eq!(result, expected, "input: {}, ast: {}", s, ast);
+        }
+    }
+
     #[test]
     fn test_eval() {
         for (s, expected) in [
@@ -349,7 +828,7 @@ mod test_parser {
             assert!(ast.get_error().is_none(), "ast: {}", ast);
             let result = ast.accept(&mut ast::AstEvaluator::new(&ast));
             assert!(
-                matches!(result, Ok(Some(got)) if expected == got),
+                matches!(result, Ok(ast::Value::Int(got)) if expected == got),
                 "expected: {:?}, got: {:?}",
                 expected,
                 result
@@ -357,6 +836,120 @@ mod test_parser {
         }
     }
 
+    #[test]
+    fn test_eval_env() {
+        for (s, expected) in [
+            ("let x = 3; x;", 3i64),
+            ("let x = 3; let y = x * 2; y - 1;", 5),
+            ("let x = 7; let y = 2; x % y + 1;", 2),
+        ] {
+            let cu = lexer::CompilationUnit::from_string("stdin", s);
+            let ast = parse(&cu);
+            assert!(ast.get_error().is_none(), "ast: {}", ast);
+            let result = ast.accept(&mut ast::AstEvaluator::new(&ast));
+            assert!(
+                matches!(result, Ok(ast::Value::Int(got)) if expected == got),
+                "input: {}, expected: {:?}, got: {:?}",
+                s,
+                expected,
+                result
+            );
+        }
+    }
+
+    #[test]
+    fn test_eval_unbound_name() {
+        let cu = lexer::CompilationUnit::from_string("stdin", "y;");
+        let ast = parse(&cu);
+        assert!(ast.get_error().is_none(), "ast: {}", ast);
+        let result = ast.accept(&mut ast::AstEvaluator::new(&ast));
+        assert!(
+            matches!(&result, Err(ast::EvalError::UnboundName { name, .. }) if name == "y"),
+            "got: {:?}",
+            result
+        );
+    }
+
+    #[test]
+    fn test_eval_comparison_and_logical() {
+        for (s, expected) in [
+            ("let x = 2; if 2 == x { return x * 2; } else { return 0; };", 4i64),
+            ("let x = 5; if 2 == x { return 0; } else { return x - 1; };", 4),
+            ("let x = 3; if x > 1 && x < 10 { return 1; } else { return 0; };", 1),
+            ("let x = 0; if x > 1 || x == 0 { return 7; } else { return 0; };", 7),
+        ] {
+            let cu = lexer::CompilationUnit::from_string("stdin", s);
+            let ast = parse(&cu);
+            assert!(ast.get_error().is_none(), "ast: {}", ast);
+            let result = ast.accept(&mut ast::AstEvaluator::new(&ast));
+            assert!(
+                matches!(result, Ok(ast::Value::Int(got)) if expected == got),
+                "input: {}, expected: {:?}, got: {:?}",
+                s,
+                expected,
+                result
+            );
+        }
+    }
+
+    #[test]
+    fn test_typed_int_literals() {
+        // the suffix round-trips through the printer
+        let cu = lexer::CompilationUnit::from_string("stdin", "let x = 3i64; x + 2u8;");
+        let ast = parse(&cu);
+        assert!(ast.get_error().is_none(), "ast: {}", ast);
+        let printer = &mut ast::AstPrinter::new(&ast);
+        assert_eq!(ast.accept(printer), "let x = 3i64;\nx + 2u8;\n", "ast: {}", ast);
+
+        // an out-of-range literal is rejected by the evaluator
+        let cu = lexer::CompilationUnit::from_string("stdin", "300u8;");
+        let ast = parse(&cu);
+        assert!(ast.get_error().is_none(), "ast: {}", ast);
+        let result = ast.accept(&mut ast::AstEvaluator::new(&ast));
+        assert!(
+            matches!(&result, Err(ast::EvalError::Overflow { msg, .. }) if msg.contains("300u8")),
+            "got: {:?}",
+            result
+        );
+    }
+
+    #[test]
+    fn test_bytecode_vm() {
+        for (s, expected) in [
+            ("1 + 2 * 3;", 7i64),
+            ("let x = 3; let y = x * 2; y - 1;", 5),
+            ("let x = 2; if 2 == x { return x * 2; } else { return 0; };", 4),
+            ("let x = 0; if x > 1 || x == 0 { return 7; } else { return 9; };", 7),
+        ] {
+            let cu = lexer::CompilationUnit::from_string("stdin", s);
+            let ast = parse(&cu);
+            assert!(ast.get_error().is_none(), "ast: {}", ast);
+            let mut compiler = ast::BytecodeCompiler::new(&ast);
+            ast.accept(&mut compiler).expect("compile failed");
+            let result = ast::Vm::new(compiler.finish()).run();
+            assert!(
+                matches!(result, Ok(ast::Value::Int(got)) if expected == got),
+                "input: {}, expected: {:?}, got: {:?}",
+                s,
+                expected,
+                result
+            );
+        }
+    }
+
+    #[test]
+    fn test_eval_value_kinds() {
+        let cu = lexer::CompilationUnit::from_string("stdin", r#""hello";"#);
+        let ast = parse(&cu);
+        assert!(ast.get_error().is_none(), "ast: {}", ast);
+        let result = ast.accept(&mut ast::AstEvaluator::new(&ast));
+        assert!(
+            matches!(&result, Ok(ast::Value::Str(s)) if s == "hello"),
+            "got: {:?}",
+            result
+        );
+    }
+
     #[test]
     fn test_string() {
         let cu = lexer::CompilationUnit::from_string(
@@ -399,4 +992,46 @@ let x = if 3 > y {
 "#
         );
     }
+
+    #[test]
+    fn test_token_source_roundtrip() {
+        // a cleanly lexed unit must rebuild its own source byte-for-byte, trivia and comments
+        // included, so formatters and source-to-source rewrites can lex → edit → print losslessly
+        for s in [
+            "",
+            "   ",
+            "let x = 3;\n",
+            "1 + 2 * 3;  # trailing\n",
+            "if x == 2 { y; } else { z; }\n",
+            "let s = \"a\\tb\\n\";\n",
+            "\n\n  fn f() -> u64 { return 0; }\n",
+        ] {
+            let cu = lexer::CompilationUnit::from_string("stdin", s);
+            let (tokens, _) = cu.get_tokens();
+            assert_eq!(tokens.to_source(&cu), s, "input: {:?}", s);
+        }
+    }
+
+    #[test]
+    fn test_cst_roundtrip() {
+        // the concrete-syntax-tree keeps the blanks and comments the abstract tree skips, so
+        // `to_source` has to reproduce each input byte-for-byte, trivia and all
+        for s in [
+            "let x = 3;",
+            "  let  x =  3 ; ",
+            "1 + 2 * 3;\n",
+            r#"if x == 2 { y; } else { z; }"#,
+            "let x = if 3 > y {\n    return 9;\n} else {\n    return 0;\n};\n",
+            "let x = 1; # a trailing comment\nlet y = 2;\n",
+            "\n\n  ;; \n",
+        ] {
+            let cu = lexer::CompilationUnit::from_string("stdin", s);
+            let ast = parse(&cu);
+            assert!(ast.get_error().is_none(), "ast: {}", ast);
+            let cst = ast.build_cst();
+            assert_eq!(cst.to_source(), s, "ast: {}", ast);
+            assert_eq!(cst.text_range().start(), 0, "ast: {}", ast);
+            assert_eq!(cst.text_range().end(), s.len(), "ast: {}", ast);
+        }
+    }
 }
@@ -8,7 +8,7 @@ use super::{
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 enum Associativity {
     Left,
-    _Right,
+    Right,
 }
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
@@ -38,6 +38,26 @@ struct TokenTrait {
     associativity: Associativity,
 }
 
+// maps an integer literal's optional type suffix to its bit-width and signedness, defaulting to
+// a signed 64-bit integer when no suffix is present
+fn int_literal_type(text: &str) -> (u32, bool) {
+    for (suffix, bits, signed) in [
+        ("i8", 8, true),
+        ("i16", 16, true),
+        ("i32", 32, true),
+        ("i64", 64, true),
+        ("u8", 8, false),
+        ("u16", 16, false),
+        ("u32", 32, false),
+        ("u64", 64, false),
+    ] {
+        if text.ends_with(suffix) {
+            return (bits, signed);
+        }
+    }
+    (64, true)
+}
+
 fn find_recovery_idx(tokens: &Tokens, mut next_token_idx: TokenIdx) -> TokenIdx {
     let Some((token_idx, token)) = tokens.find_next_non_blank_token(next_token_idx) else {
         return next_token_idx;
@@ -103,6 +123,32 @@ fn find_recovery_idx(tokens: &Tokens, mut next_token_idx: TokenIdx) -> TokenIdx
     }
 }
 
+// the index just past the next non-blank token at or after `next_token_idx`, or the
+// end-of-input sentinel if none remains; used to force the statement loop forward when recovery
+// could not itself advance
+fn advance_past_token(tokens: &Tokens, next_token_idx: TokenIdx) -> TokenIdx {
+    match tokens.find_next_non_blank_token(next_token_idx) {
+        Some((token_idx, _)) => token_idx + 1,
+        None => tokens.invalid_token_idx(),
+    }
+}
+
+// scans from the offending token to the next statement/block synchronization point (`;`, `}`,
+// `else`, or end of input) and returns its index, which is the offending token's own index when
+// that token is already one, so expression-level recovery can resume from a sane boundary after
+// planting an `Expr::Error` hole
+fn recover_to_sync_point(tokens: &Tokens, mut next_token_idx: TokenIdx) -> TokenIdx {
+    while let Some((token_idx, token)) = tokens.find_next_non_blank_token(next_token_idx) {
+        match token.get_kind() {
+            lexer::TokenKind::SemiColon
+            | lexer::TokenKind::RCurlyBrace
+            | lexer::TokenKind::KwElse => return token_idx,
+            _ => next_token_idx = token_idx + 1,
+        }
+    }
+    tokens.invalid_token_idx()
+}
+
 // either returns error or UnaryOp node
 fn must_be_i64_after_dash_sign(
     ast: &mut ast::Ast<ParseError>,
@@ -122,13 +168,20 @@ fn must_be_i64_after_dash_sign(
     };
 
     match num_token.get_kind() {
-        lexer::TokenKind::I64 => ParseResult::new_node(
-            ast::AstNode::Expression(ast::Expr::Negation {
-                operator: dash_token_idx,
-                operand: ast.push_node(ast::AstNode::Expression(ast::Expr::I64(num_token_idx))),
-            }),
-            num_token_idx + 1,
-        ),
+        lexer::TokenKind::I64 => {
+            let (bits, signed) = int_literal_type(ast.get_token_str(num_token_idx));
+            ParseResult::new_node(
+                ast::AstNode::Expression(ast::Expr::Negation {
+                    operator: dash_token_idx,
+                    operand: ast.push_node(ast::AstNode::Expression(ast::Expr::Int {
+                        token_idx: num_token_idx,
+                        bits,
+                        signed,
+                    })),
+                }),
+                num_token_idx + 1,
+            )
+        }
         lexer::TokenKind::Minus => ParseResult::new_error_unexpected_token(
             format!(
                 "`{}` cannot be chained",
@@ -152,6 +205,7 @@ pub(super) fn parse_module(ast: &mut ast::Ast<ParseError>, mut next_token_idx: T
     let mut module_node = ast::AstNode::new_module_with_capacity(50);
     let mut error = ParseError::no_error();
     loop {
+        let stmt_start_idx = next_token_idx;
         match parse_statement(ast, next_token_idx) {
             Ok(HappyPath::Node {
                 node: statement,
@@ -166,40 +220,199 @@ pub(super) fn parse_module(ast: &mut ast::Ast<ParseError>, mut next_token_idx: T
             }
             Err(e) => {
                 error = error.add_new_error(e);
-                next_token_idx = find_recovery_idx(ast.get_tokens(), next_token_idx);
+                let recovery_idx = find_recovery_idx(ast.get_tokens(), next_token_idx);
+                // guarantee forward progress: a recovery point that does not move past the token
+                // this statement started on would let the loop re-parse the same input forever, so
+                // step over one token by hand in that case
+                next_token_idx = if recovery_idx > stmt_start_idx {
+                    recovery_idx
+                } else {
+                    advance_past_token(ast.get_tokens(), stmt_start_idx)
+                };
             }
         }
     }
 
+    // fold in any diagnostics collected by expression-level recovery so a single parse surfaces
+    // every problem, not just the first one that reached the statement loop
+    for buffered in ast.take_accumulated_errors() {
+        error = error.add_new_error(buffered);
+    }
+
     ast.set_error(error);
     ast.set_module(module_node);
 }
 
-fn parse_type_from_colon(ast: &mut ast::Ast<ParseError>, colon_token_idx: TokenIdx) -> ParseResult {
+// recursively parses a type expression starting from the first non-blank token at or after
+// `start_search_idx`; reusable wherever a type annotation is expected
+//
+// the grammar covers bare names (`T`), generic application (`List<T>`, `Map<K, V>`), tuples
+// (`(A, B)`), and function types (`fn(A, B) -> C`). nested generics close one level per `>`
+// token, which works because the lexer only ever emits a single `>` (there is no `>>` token).
+fn parse_type(ast: &mut ast::Ast<ParseError>, start_search_idx: TokenIdx) -> ParseResult {
     let no_type_err_fn = || "expected a type expression";
 
-    let Some((type_start_token_idx, type_start_token)) = ast
-        .get_tokens()
-        .find_next_non_blank_token(colon_token_idx + 1)
+    let Some((type_start_token_idx, type_start_token)) =
+        ast.get_tokens().find_next_non_blank_token(start_search_idx)
     else {
-        return ParseResult::new_error_unexpected_eof(no_type_err_fn(), colon_token_idx + 1);
+        return ParseResult::new_error_unexpected_eof(no_type_err_fn(), start_search_idx);
     };
 
     match type_start_token.get_kind() {
-        lexer::TokenKind::Identifier { .. } => ParseResult::new_node(
-            ast::AstNode::Annotation(ast::Anno::Type {
-                token_idx: type_start_token_idx,
-            }),
-            type_start_token_idx + 1,
-        ),
+        lexer::TokenKind::Identifier { .. } => {
+            // an identifier immediately followed by `<` is a generic application
+            match ast
+                .get_tokens()
+                .find_next_non_blank_token(type_start_token_idx + 1)
+            {
+                Some((lt_token_idx, lt_token))
+                    if lt_token.get_kind() == &lexer::TokenKind::Lt =>
+                {
+                    parse_generic_type(ast, type_start_token_idx, lt_token_idx)
+                }
+                _ => ParseResult::new_node(
+                    ast::AstNode::Annotation(ast::Anno::Type {
+                        token_idx: type_start_token_idx,
+                    }),
+                    type_start_token_idx + 1,
+                ),
+            }
+        }
+        lexer::TokenKind::LParen => parse_tuple_type(ast, type_start_token_idx),
+        lexer::TokenKind::KwFn => parse_func_type(ast, type_start_token_idx),
         _ => ParseResult::new_error_unexpected_token(
             no_type_err_fn(),
-            colon_token_idx,
+            start_search_idx,
             type_start_token_idx,
         ),
     }
 }
 
+// parses a comma-separated list of types up to and including a `closer` token, assuming the
+// opening delimiter is at `open_token_idx`; an empty list and a trailing `closer` are both allowed
+fn parse_type_sequence(
+    ast: &mut ast::Ast<ParseError>,
+    open_token_idx: TokenIdx,
+    closer: lexer::TokenKind,
+) -> Result<(Vec<ast::AstNodeIdx>, TokenIdx), ParseError> {
+    let mut elems = Vec::new();
+    let mut next_token_idx = open_token_idx + 1;
+    loop {
+        // empty list, or the `closer` after the last type
+        if let Some((closer_token_idx, closer_token)) =
+            ast.get_tokens().find_next_non_blank_token(next_token_idx)
+        {
+            if closer_token.get_kind() == &closer {
+                return Ok((elems, closer_token_idx));
+            }
+        }
+
+        let HappyPath::Node {
+            node: elem,
+            next_token_idx: after_elem_token_idx,
+        } = parse_type(ast, next_token_idx)?
+        else {
+            panic!("BUG: parse_type should always return a node")
+        };
+        elems.push(ast.push_node(elem));
+
+        let separator_token_idx = must_find(
+            ast.get_tokens(),
+            open_token_idx,
+            after_elem_token_idx,
+            || {
+                format!(
+                    "expected `{}` or `{}` after a type",
+                    lexer::TokenKind::Comma.get_string_repr(),
+                    closer.get_string_repr()
+                )
+            },
+            |token_kind| token_kind == &lexer::TokenKind::Comma || token_kind == &closer,
+        )?;
+        if ast.get_tokens()[separator_token_idx].get_kind() == &closer {
+            return Ok((elems, separator_token_idx));
+        }
+        next_token_idx = separator_token_idx + 1;
+    }
+}
+
+// first token is an identifier `head`, immediately followed by the `<` at `lt_token_idx`
+fn parse_generic_type(
+    ast: &mut ast::Ast<ParseError>,
+    head_token_idx: TokenIdx,
+    lt_token_idx: TokenIdx,
+) -> ParseResult {
+    let (args, gt_token_idx) = parse_type_sequence(ast, lt_token_idx, lexer::TokenKind::Gt)?;
+    ParseResult::new_node(
+        ast::AstNode::Annotation(ast::Anno::Generic {
+            head: head_token_idx,
+            args,
+        }),
+        gt_token_idx + 1,
+    )
+}
+
+// first token is the `(` at `lparen_token_idx`
+fn parse_tuple_type(ast: &mut ast::Ast<ParseError>, lparen_token_idx: TokenIdx) -> ParseResult {
+    let (elems, rparen_token_idx) =
+        parse_type_sequence(ast, lparen_token_idx, lexer::TokenKind::RParen)?;
+    ParseResult::new_node(
+        ast::AstNode::Annotation(ast::Anno::Tuple { elems }),
+        rparen_token_idx + 1,
+    )
+}
+
+// first token is the `fn` at `fn_kw_token_idx`
+fn parse_func_type(ast: &mut ast::Ast<ParseError>, fn_kw_token_idx: TokenIdx) -> ParseResult {
+    let lparen_token_idx = must_find(
+        ast.get_tokens(),
+        fn_kw_token_idx,
+        fn_kw_token_idx + 1,
+        || {
+            format!(
+                "expected a `{}` after `{}`",
+                lexer::TokenKind::LParen.get_string_repr(),
+                lexer::TokenKind::KwFn.get_string_repr()
+            )
+        },
+        |token_kind| token_kind == &lexer::TokenKind::LParen,
+    )?;
+
+    let (params, rparen_token_idx) =
+        parse_type_sequence(ast, lparen_token_idx, lexer::TokenKind::RParen)?;
+
+    let arrow_token_idx = must_find(
+        ast.get_tokens(),
+        fn_kw_token_idx,
+        rparen_token_idx + 1,
+        || {
+            format!(
+                "expected `{}` before a function type's return type",
+                lexer::TokenKind::Arrow.get_string_repr()
+            )
+        },
+        |token_kind| token_kind == &lexer::TokenKind::Arrow,
+    )?;
+
+    let no_ret_err_fn = || "expected a return type";
+    let HappyPath::Node {
+        node: ret,
+        next_token_idx: after_ret_token_idx,
+    } = parse_type(ast, arrow_token_idx + 1).map_err(|e| e.add_error_context(no_ret_err_fn()))?
+    else {
+        return ParseResult::new_error_unexpected_eof(no_ret_err_fn(), arrow_token_idx);
+    };
+
+    ParseResult::new_node(
+        ast::AstNode::Annotation(ast::Anno::Func {
+            fn_kw: fn_kw_token_idx,
+            params,
+            ret: ast.push_node(ret),
+        }),
+        after_ret_token_idx,
+    )
+}
+
 // either returns a definiton statement or an error, never returns `IntermediateResult::Finished`
 //
 // first token is `let` or `var`
@@ -218,10 +431,12 @@ fn parse_definition_statement(
             kw_kind.get_string_repr()
         )
     };
+    // parse the left-hand side above assignment's precedence so the definition's own `=` is not
+    // swallowed as an assignment expression
     let HappyPath::Node {
         node: lhs_expr,
         next_token_idx: after_lhs_token_idx,
-    } = parse_expression(ast, kw_token_idx + 1, Precedence::new(0))
+    } = parse_expression(ast, kw_token_idx + 1, Precedence::new(1))
         .map_err(|e| e.add_error_context(no_lhs_expr_err_fn()))?
     else {
         return ParseResult::new_error_unexpected_eof(no_lhs_expr_err_fn(), kw_token_idx);
@@ -245,7 +460,7 @@ fn parse_definition_statement(
             let HappyPath::Node {
                 node: type_expr,
                 next_token_idx: after_type_token_idx,
-            } = parse_type_from_colon(ast, colon_token_idx_)
+            } = parse_type(ast, colon_token_idx_ + 1)
                 .map_err(|e| e.add_error_context(no_type_error_fn()))?
             else {
                 return ParseResult::new_error_unexpected_eof(no_type_error_fn(), colon_token_idx_);
@@ -330,21 +545,55 @@ fn try_parse_expression_statement(
     };
 
     // ;
-    let semicolon_token_idx = must_find(
-        ast.get_tokens(),
-        next_token_idx,
-        after_expr_token_idx,
-        || {
-            format!(
-                "statement must end with `{}`",
-                lexer::TokenKind::SemiColon.get_string_repr()
-            )
-        },
-        |token_kind| token_kind == &lexer::TokenKind::SemiColon,
-    )?;
+    //
+    // an expression with a block (`if`/`while`/`loop`/`match`/`{ .. }`) may stand as a statement
+    // without a trailing `;`, matching Rust's statement grammar; a `;` is still consumed when
+    // present. every other expression statement must end with `;`.
+    let stmt_end_token_idx = if is_expr_with_block(&node) {
+        match ast
+            .get_tokens()
+            .find_next_non_blank_token(after_expr_token_idx)
+        {
+            Some((semicolon_token_idx, token))
+                if token.get_kind() == &lexer::TokenKind::SemiColon =>
+            {
+                semicolon_token_idx + 1
+            }
+            _ => after_expr_token_idx,
+        }
+    } else {
+        let semicolon_token_idx = must_find(
+            ast.get_tokens(),
+            next_token_idx,
+            after_expr_token_idx,
+            || {
+                format!(
+                    "statement must end with `{}`",
+                    lexer::TokenKind::SemiColon.get_string_repr()
+                )
+            },
+            |token_kind| token_kind == &lexer::TokenKind::SemiColon,
+        )?;
+        semicolon_token_idx + 1
+    };
     ParseResult::new_node(
         ast::AstNode::Statement(ast::Stat::Expression(ast.push_node(node))),
-        semicolon_token_idx + 1,
+        stmt_end_token_idx,
+    )
+}
+
+// an expression "with a block" ends in a `}` (`if`/`while`/`loop`/`match`/a bare block), so it can
+// stand as a statement without a trailing `;`
+fn is_expr_with_block(node: &ast::AstNode) -> bool {
+    matches!(
+        node,
+        ast::AstNode::Expression(
+            ast::Expr::If { .. }
+                | ast::Expr::While { .. }
+                | ast::Expr::Loop { .. }
+                | ast::Expr::Match { .. }
+                | ast::Expr::Block { .. }
+        )
     )
 }
 
@@ -406,28 +655,42 @@ fn parse_statement(ast: &mut ast::Ast<ParseError>, next_token_idx: TokenIdx) ->
 
 fn get_precedence(token: &lexer::Token) -> Option<TokenTrait> {
     match token.get_kind() {
-        lexer::TokenKind::EqEq => Some(TokenTrait {
+        // assignment binds looser than every other operator and associates to the right, so
+        // `a = b = c` parses as `a = (b = c)`
+        lexer::TokenKind::Eq => Some(TokenTrait {
+            precedence: Precedence::new(0),
+            associativity: Associativity::Right,
+        }),
+        lexer::TokenKind::PipePipe => Some(TokenTrait {
             precedence: Precedence::new(1),
             associativity: Associativity::Left,
         }),
+        lexer::TokenKind::AmpAmp => Some(TokenTrait {
+            precedence: Precedence::new(2),
+            associativity: Associativity::Left,
+        }),
+        lexer::TokenKind::EqEq => Some(TokenTrait {
+            precedence: Precedence::new(3),
+            associativity: Associativity::Left,
+        }),
         lexer::TokenKind::Ne => Some(TokenTrait {
-            precedence: Precedence::new(1),
+            precedence: Precedence::new(3),
             associativity: Associativity::Left,
         }),
         lexer::TokenKind::Lt => Some(TokenTrait {
-            precedence: Precedence::new(1),
+            precedence: Precedence::new(3),
             associativity: Associativity::Left,
         }),
         lexer::TokenKind::Le => Some(TokenTrait {
-            precedence: Precedence::new(1),
+            precedence: Precedence::new(3),
             associativity: Associativity::Left,
         }),
         lexer::TokenKind::Gt => Some(TokenTrait {
-            precedence: Precedence::new(1),
+            precedence: Precedence::new(3),
             associativity: Associativity::Left,
         }),
         lexer::TokenKind::Ge => Some(TokenTrait {
-            precedence: Precedence::new(1),
+            precedence: Precedence::new(3),
             associativity: Associativity::Left,
         }),
         lexer::TokenKind::Plus => Some(TokenTrait {
@@ -442,6 +705,12 @@ fn get_precedence(token: &lexer::Token) -> Option<TokenTrait> {
             precedence: Precedence::new(6),
             associativity: Associativity::Left,
         }),
+        // exponentiation binds tighter than `*`/`/` and associates to the right, so
+        // `2 ** 3 ** 2` parses as `2 ** (3 ** 2)`
+        lexer::TokenKind::StarStar => Some(TokenTrait {
+            precedence: Precedence::new(7),
+            associativity: Associativity::Right,
+        }),
         lexer::TokenKind::Slash => Some(TokenTrait {
             precedence: Precedence::new(6),
             associativity: Associativity::Left,
@@ -455,12 +724,10 @@ fn get_precedence(token: &lexer::Token) -> Option<TokenTrait> {
 }
 
 fn is_end_of_expression(token: &lexer::Token) -> bool {
-    [
-        lexer::TokenKind::Eq,
-        lexer::TokenKind::SemiColon,
-        lexer::TokenKind::RParen,
-    ]
-    .contains(token.get_kind())
+    // `=` is no longer a hard terminator: it parses as a right-associative assignment operator in
+    // a bare expression. A definition statement instead stops its left-hand side before `=` by
+    // parsing it with a minimum precedence above assignment's.
+    [lexer::TokenKind::SemiColon, lexer::TokenKind::RParen].contains(token.get_kind())
 }
 
 fn can_shift_with_op(
@@ -504,6 +771,19 @@ fn parse_expression(
     ast: &mut ast::Ast<ParseError>,
     next_token_idx: TokenIdx,
     min_precedence: Precedence,
+) -> ParseResult {
+    parse_expression_in_ctx(ast, next_token_idx, min_precedence, false)
+}
+
+/// Parses an expression, threading `in_condition` so that `let` terms are only accepted directly
+/// in the condition of an `if`/`while` expression. The flag is propagated across `&&`/`||` so a
+/// let chain like `if let Some(x) = a && x > 0` parses, but is dropped everywhere else (inside
+/// another operator, a call argument, or parentheses) so `let` there becomes a parse error.
+fn parse_expression_in_ctx(
+    ast: &mut ast::Ast<ParseError>,
+    next_token_idx: TokenIdx,
+    min_precedence: Precedence,
+    in_condition: bool,
 ) -> ParseResult {
     // nothing
     let Some((expr_start_token_idx, expr_start_token)) =
@@ -522,24 +802,155 @@ fn parse_expression(
         return parse_return_expression(ast, expr_start_token_idx);
     }
 
-    // must be something or return error
-    let HappyPath::Node {
-        node: mut lhs,
-        next_token_idx: after_lhs_token_idx,
-    } = parse_primary(ast, expr_start_token_idx, &expr_start_token.clone())?
-    // following doens't seem to be a very useful error context
-    //.map_err(|e| e.add_error_context("an expression must start with an expression"))?
-    else {
-        panic!("BUG: parse_primary should always return a node")
+    // try while
+    if expr_start_token.get_kind() == &lexer::TokenKind::KwWhile {
+        return parse_while_expression(ast, expr_start_token_idx);
+    }
+
+    // try loop
+    if expr_start_token.get_kind() == &lexer::TokenKind::KwLoop {
+        return parse_loop_expression(ast, expr_start_token_idx);
+    }
+
+    // try match
+    if expr_start_token.get_kind() == &lexer::TokenKind::KwMatch {
+        return parse_match_expression(ast, expr_start_token_idx);
+    }
+
+    // try break / continue
+    if expr_start_token.get_kind() == &lexer::TokenKind::KwBreak {
+        return parse_break_expression(ast, expr_start_token_idx);
+    }
+    if expr_start_token.get_kind() == &lexer::TokenKind::KwContinue {
+        return ParseResult::new_node(
+            ast::AstNode::Expression(ast::Expr::Continue {
+                continue_kw: expr_start_token_idx,
+            }),
+            expr_start_token_idx + 1,
+        );
+    }
+
+    // a `let` term is only legal directly in a condition; a postfix operator would never follow
+    // one, so it bypasses the call/index loop below
+    if expr_start_token.get_kind() == &lexer::TokenKind::KwLet {
+        if !in_condition {
+            return ParseResult::new_error_unexpected_token(
+                "`let` is only supported directly in conditions of `if`/`while` expressions",
+                expr_start_token_idx,
+                expr_start_token_idx,
+            );
+        }
+        let HappyPath::Node {
+            node: let_term,
+            next_token_idx: after_let_token_idx,
+        } = parse_let_condition(ast, expr_start_token_idx)?
+        else {
+            panic!("BUG: parse_let_condition should always return a node")
+        };
+        return parse_binary_shift(ast, let_term, after_let_token_idx, min_precedence, in_condition);
+    }
+
+    // a leading prefix operator (`-`, `!`) binds tighter than any binary operator, so
+    // `-a * b` parses as `(-a) * b` and chaining like `--x`/`!!x` falls out of the recursion
+    let (mut lhs, after_lhs_token_idx) = match expr_start_token.get_kind() {
+        lexer::TokenKind::Minus | lexer::TokenKind::Not => {
+            let HappyPath::Node {
+                node,
+                next_token_idx,
+            } = parse_prefix_expression(ast, expr_start_token_idx, &expr_start_token.clone())?
+            else {
+                panic!("BUG: parse_prefix_expression should always return a node")
+            };
+            (node, next_token_idx)
+        }
+        // must be something or return error
+        _ => {
+            let HappyPath::Node {
+                node,
+                next_token_idx,
+            } = parse_primary(ast, expr_start_token_idx, &expr_start_token.clone())?
+            // following doens't seem to be a very useful error context
+            //.map_err(|e| e.add_error_context("an expression must start with an expression"))?
+            else {
+                panic!("BUG: parse_primary should always return a node")
+            };
+            (node, next_token_idx)
+        }
     };
 
+    // postfix operators (call `f(a, b)`, index `a[i]`) bind tighter than any binary operator, so
+    // the loop runs to exhaustion here before the binary shift loop below
     let mut next_token_idx = after_lhs_token_idx;
+    loop {
+        let Some((postfix_token_idx, postfix_token)) =
+            ast.get_tokens().find_next_non_blank_token(next_token_idx)
+        else {
+            break;
+        };
+        match postfix_token.get_kind() {
+            lexer::TokenKind::LParen => {
+                let callee_node_idx = ast.push_node(lhs);
+                let HappyPath::Node {
+                    node,
+                    next_token_idx: after_call_token_idx,
+                } = parse_call_expression(ast, callee_node_idx, postfix_token_idx)?
+                else {
+                    panic!("BUG: parse_call_expression should always return a node")
+                };
+                lhs = node;
+                next_token_idx = after_call_token_idx;
+            }
+            lexer::TokenKind::LBracket => {
+                let base_node_idx = ast.push_node(lhs);
+                let HappyPath::Node {
+                    node,
+                    next_token_idx: after_index_token_idx,
+                } = parse_index_expression(ast, base_node_idx, postfix_token_idx)?
+                else {
+                    panic!("BUG: parse_index_expression should always return a node")
+                };
+                lhs = node;
+                next_token_idx = after_index_token_idx;
+            }
+            _ => break,
+        }
+    }
+
+    parse_binary_shift(ast, lhs, next_token_idx, min_precedence, in_condition)
+}
+
+// the Pratt shift loop shared by the normal and let-condition entry points: given a parsed `lhs`
+// and the index just past it, folds in any following binary operators at or above
+// `min_precedence`. `in_condition` is carried only across `&&`/`||` so a `let` term stays legal on
+// either side of a let chain, and a `||` joining a let term is rejected outright as rustc does.
+fn parse_binary_shift(
+    ast: &mut ast::Ast<ParseError>,
+    mut lhs: ast::AstNode,
+    mut next_token_idx: TokenIdx,
+    min_precedence: Precedence,
+    in_condition: bool,
+) -> ParseResult {
     // if there is something, try to see if it is binary operation
     //     if it is, should we shift or reduce?
     while let Some((op_token_idx, op_token, min_precedence)) =
         can_shift_with_op(ast.get_tokens(), next_token_idx, min_precedence)
     {
         let op_token = op_token.clone();
+        let is_logical = matches!(
+            op_token.get_kind(),
+            lexer::TokenKind::AmpAmp | lexer::TokenKind::PipePipe
+        );
+        // a let chain may not be joined with `||`; catch a let term sitting on either side of one
+        if in_condition
+            && op_token.get_kind() == &lexer::TokenKind::PipePipe
+            && is_let_expression(&lhs)
+        {
+            return ParseResult::new_error_unexpected_token(
+                "`||` operators are not supported in let chain expressions",
+                op_token_idx,
+                op_token_idx,
+            );
+        }
         // if it is binary op, then there must be an expression after op sign
         let no_rhs_expr_err_fn = || {
             format!(
@@ -550,25 +961,110 @@ fn parse_expression(
         let HappyPath::Node {
             node: rhs,
             next_token_idx: after_rhs_token_idx,
-        } = parse_expression(ast, op_token_idx + 1, min_precedence)
+        } = parse_expression_in_ctx(ast, op_token_idx + 1, min_precedence, in_condition && is_logical)
             .map_err(|e| e.add_error_context(no_rhs_expr_err_fn()))?
         else {
             return ParseResult::new_error_unexpected_eof(no_rhs_expr_err_fn(), op_token_idx);
         };
+        if in_condition
+            && op_token.get_kind() == &lexer::TokenKind::PipePipe
+            && is_let_expression(&rhs)
+        {
+            return ParseResult::new_error_unexpected_token(
+                "`||` operators are not supported in let chain expressions",
+                op_token_idx,
+                op_token_idx,
+            );
+        }
 
         let lhs_node_idx = ast.push_node(lhs);
         let rhs_node_idx = ast.push_node(rhs);
-        lhs = ast::AstNode::Expression(ast::Expr::ArithmeticOrLogical {
-            operator: op_token_idx,
-            lhs: lhs_node_idx,
-            rhs: rhs_node_idx,
-        });
+        lhs = if op_token.get_kind() == &lexer::TokenKind::Eq {
+            ast::AstNode::Expression(ast::Expr::Assign {
+                target: lhs_node_idx,
+                eq: op_token_idx,
+                value: rhs_node_idx,
+            })
+        } else {
+            ast::AstNode::Expression(ast::Expr::ArithmeticOrLogical {
+                operator: op_token_idx,
+                lhs: lhs_node_idx,
+                rhs: rhs_node_idx,
+            })
+        };
         next_token_idx = after_rhs_token_idx;
     }
 
     ParseResult::new_node(lhs, next_token_idx)
 }
 
+fn is_let_expression(node: &ast::AstNode) -> bool {
+    matches!(node, ast::AstNode::Expression(ast::Expr::Let { .. }))
+}
+
+// parses a `let PATTERN = EXPR` condition term; the value is parsed above the `&&`/`||` precedence
+// so the surrounding shift loop keeps the chain operators at condition level
+fn parse_let_condition(
+    ast: &mut ast::Ast<ParseError>,
+    let_kw_token_idx: TokenIdx,
+) -> ParseResult {
+    let no_pattern_err_fn = || {
+        format!(
+            "expected a pattern after `{}`",
+            lexer::TokenKind::KwLet.get_string_repr()
+        )
+    };
+    let HappyPath::Node {
+        node: pattern,
+        next_token_idx: after_pattern_token_idx,
+    } = parse_pattern(ast, let_kw_token_idx + 1)
+        .map_err(|e| e.add_error_context(no_pattern_err_fn()))?
+    else {
+        return ParseResult::new_error_unexpected_eof(no_pattern_err_fn(), let_kw_token_idx);
+    };
+    let pattern_node_idx = ast.push_node(pattern);
+
+    let eq_token_idx = must_find(
+        ast.get_tokens(),
+        let_kw_token_idx,
+        after_pattern_token_idx,
+        || {
+            format!(
+                "expected a `{}` after a `{}` pattern",
+                lexer::TokenKind::Eq.get_string_repr(),
+                lexer::TokenKind::KwLet.get_string_repr()
+            )
+        },
+        |token_kind| token_kind == &lexer::TokenKind::Eq,
+    )?;
+
+    // the logical operators join condition terms, so the value must stop short of them
+    let no_value_err_fn = || {
+        format!(
+            "expected an expression after `{}` in a `{}` condition",
+            lexer::TokenKind::Eq.get_string_repr(),
+            lexer::TokenKind::KwLet.get_string_repr()
+        )
+    };
+    let HappyPath::Node {
+        node: value,
+        next_token_idx: after_value_token_idx,
+    } = parse_expression(ast, eq_token_idx + 1, Precedence::new(3))
+        .map_err(|e| e.add_error_context(no_value_err_fn()))?
+    else {
+        return ParseResult::new_error_unexpected_eof(no_value_err_fn(), eq_token_idx);
+    };
+
+    ParseResult::new_node(
+        ast::AstNode::Expression(ast::Expr::Let {
+            pattern_node_idx,
+            eq_token: eq_token_idx,
+            value_node_idx: ast.push_node(value),
+        }),
+        after_value_token_idx,
+    )
+}
+
 fn parse_return_expression(
     ast: &mut ast::Ast<ParseError>,
     return_kw_token_idx: TokenIdx,
@@ -597,39 +1093,519 @@ fn parse_return_expression(
     )
 }
 
-fn parse_block_expression(
+fn parse_while_expression(
     ast: &mut ast::Ast<ParseError>,
-    lcurlybrace_token_idx: TokenIdx,
+    while_kw_token_idx: TokenIdx,
 ) -> ParseResult {
-    let mut statements_node_indices = Vec::with_capacity(25);
-    let mut next_token_idx = lcurlybrace_token_idx + 1;
-    let mut error = ParseError::no_error();
-    loop {
-        match ast.get_tokens().find_next_non_blank_token(next_token_idx) {
-            Some((rcurlybrace_token_idx, token))
-                if token.get_kind() == &lexer::TokenKind::RCurlyBrace =>
-            {
-                return ParseResult::new_node(
-                    ast::AstNode::Expression(ast::Expr::Block {
-                        lcurlybracket: lcurlybrace_token_idx,
-                        statements_node_indices,
-                        rcurlybracket: rcurlybrace_token_idx,
-                    }),
-                    rcurlybrace_token_idx + 1,
-                );
-            }
-            _ => {}
-        }
+    let no_cond_expr_err_fn = || {
+        format!(
+            "expected a logical expression after `{}`",
+            lexer::TokenKind::KwWhile.get_string_repr()
+        )
+    };
+    let HappyPath::Node {
+        node: cond_expr,
+        next_token_idx: after_cond_expr_token_idx,
+    } = parse_expression_in_ctx(ast, while_kw_token_idx + 1, Precedence::new(0), true)
+        .map_err(|e| e.add_error_context(no_cond_expr_err_fn()))?
+    else {
+        return ParseResult::new_error_unexpected_eof(no_cond_expr_err_fn(), while_kw_token_idx);
+    };
 
-        match parse_statement(ast, next_token_idx) {
-            Ok(HappyPath::Node {
-                node: statement,
-                next_token_idx: token_idx,
-            }) => {
-                statements_node_indices.push(ast.push_node(statement));
-                next_token_idx = token_idx;
+    let body_lcurlybrace_token_idx = must_find(
+        ast.get_tokens(),
+        while_kw_token_idx,
+        after_cond_expr_token_idx,
+        || {
+            format!(
+                "expected a `{}` after `{}`",
+                lexer::TokenKind::LCurlyBrace.get_string_repr(),
+                lexer::TokenKind::KwWhile.get_string_repr()
+            )
+        },
+        |token_kind| token_kind == &lexer::TokenKind::LCurlyBrace,
+    )?;
+
+    let no_valid_block_err_fn = || "not a valid block";
+    let HappyPath::Node {
+        node: body_block,
+        next_token_idx: after_body_block_token_idx,
+    } = parse_block_expression(ast, body_lcurlybrace_token_idx)
+        .map_err(|e| e.add_error_context(no_valid_block_err_fn()))?
+    else {
+        return ParseResult::new_error_unexpected_eof(no_valid_block_err_fn(), while_kw_token_idx);
+    };
+
+    ParseResult::new_node(
+        ast::AstNode::Expression(ast::Expr::While {
+            while_kw: while_kw_token_idx,
+            condition_node_idx: ast.push_node(cond_expr),
+            body_block_node_idx: ast.push_node(body_block),
+        }),
+        after_body_block_token_idx,
+    )
+}
+
+fn parse_loop_expression(
+    ast: &mut ast::Ast<ParseError>,
+    loop_kw_token_idx: TokenIdx,
+) -> ParseResult {
+    let body_lcurlybrace_token_idx = must_find(
+        ast.get_tokens(),
+        loop_kw_token_idx,
+        loop_kw_token_idx + 1,
+        || {
+            format!(
+                "expected a `{}` after `{}`",
+                lexer::TokenKind::LCurlyBrace.get_string_repr(),
+                lexer::TokenKind::KwLoop.get_string_repr()
+            )
+        },
+        |token_kind| token_kind == &lexer::TokenKind::LCurlyBrace,
+    )?;
+
+    let no_valid_block_err_fn = || "not a valid block";
+    let HappyPath::Node {
+        node: body_block,
+        next_token_idx: after_body_block_token_idx,
+    } = parse_block_expression(ast, body_lcurlybrace_token_idx)
+        .map_err(|e| e.add_error_context(no_valid_block_err_fn()))?
+    else {
+        return ParseResult::new_error_unexpected_eof(no_valid_block_err_fn(), loop_kw_token_idx);
+    };
+
+    ParseResult::new_node(
+        ast::AstNode::Expression(ast::Expr::Loop {
+            loop_kw: loop_kw_token_idx,
+            body_block_node_idx: ast.push_node(body_block),
+        }),
+        after_body_block_token_idx,
+    )
+}
+
+// `break` optionally carries a value expression; a bare `break` is detected by peeking for a
+// `;` or `}` immediately after the keyword, mirroring how `return` is parsed
+fn parse_break_expression(
+    ast: &mut ast::Ast<ParseError>,
+    break_kw_token_idx: TokenIdx,
+) -> ParseResult {
+    let bare_break = ast
+        .get_tokens()
+        .find_next_non_blank_token(break_kw_token_idx + 1)
+        .map_or(true, |(_, token)| {
+            token.get_kind() == &lexer::TokenKind::SemiColon
+                || token.get_kind() == &lexer::TokenKind::RCurlyBrace
+        });
+    if bare_break {
+        return ParseResult::new_node(
+            ast::AstNode::Expression(ast::Expr::Break {
+                break_kw: break_kw_token_idx,
+                expression_node_idx: None,
+            }),
+            break_kw_token_idx + 1,
+        );
+    }
+
+    let no_break_expr_err_fn = || {
+        format!(
+            "expected an expression after `{}`",
+            lexer::TokenKind::KwBreak.get_string_repr()
+        )
+    };
+    let HappyPath::Node {
+        node: expr,
+        next_token_idx: after_expr_token_idx,
+    } = parse_expression(ast, break_kw_token_idx + 1, Precedence::new(0))
+        .map_err(|e| e.add_error_context(no_break_expr_err_fn()))?
+    else {
+        return ParseResult::new_error_unexpected_eof(no_break_expr_err_fn(), break_kw_token_idx);
+    };
+
+    ParseResult::new_node(
+        ast::AstNode::Expression(ast::Expr::Break {
+            break_kw: break_kw_token_idx,
+            expression_node_idx: Some(ast.push_node(expr)),
+        }),
+        after_expr_token_idx,
+    )
+}
+
+fn parse_match_expression(
+    ast: &mut ast::Ast<ParseError>,
+    match_kw_token_idx: TokenIdx,
+) -> ParseResult {
+    // scrutinee
+    let no_scrutinee_err_fn = || {
+        format!(
+            "expected an expression after `{}`",
+            lexer::TokenKind::KwMatch.get_string_repr()
+        )
+    };
+    let HappyPath::Node {
+        node: scrutinee,
+        next_token_idx: after_scrutinee_token_idx,
+    } = parse_expression(ast, match_kw_token_idx + 1, Precedence::new(0))
+        .map_err(|e| e.add_error_context(no_scrutinee_err_fn()))?
+    else {
+        return ParseResult::new_error_unexpected_eof(no_scrutinee_err_fn(), match_kw_token_idx);
+    };
+    let scrutinee_node_idx = ast.push_node(scrutinee);
+
+    // `{`
+    let lcurlybrace_token_idx = must_find(
+        ast.get_tokens(),
+        match_kw_token_idx,
+        after_scrutinee_token_idx,
+        || {
+            format!(
+                "expected a `{}` after a match scrutinee",
+                lexer::TokenKind::LCurlyBrace.get_string_repr()
+            )
+        },
+        |token_kind| token_kind == &lexer::TokenKind::LCurlyBrace,
+    )?;
+
+    let mut arms = Vec::new();
+    let mut error = ParseError::no_error();
+    let mut next_token_idx = lcurlybrace_token_idx + 1;
+    loop {
+        // closing `}` (also the empty-arm-list case)
+        match ast.get_tokens().find_next_non_blank_token(next_token_idx) {
+            None => {
+                error = error.add_new_error(ParseError::SingleParseError(
+                    SingleParseError::UnexpectedEof {
+                        msg: format!(
+                            "no matching `{}`",
+                            lexer::TokenKind::RCurlyBrace.get_string_repr()
+                        ),
+                        ctx_token_idx: lcurlybrace_token_idx,
+                    },
+                ));
+                break;
             }
-            Ok(HappyPath::Finished) => {
+            Some((rcurlybrace_token_idx, token))
+                if token.get_kind() == &lexer::TokenKind::RCurlyBrace =>
+            {
+                return finish_match(
+                    error,
+                    match_kw_token_idx,
+                    scrutinee_node_idx,
+                    arms,
+                    rcurlybrace_token_idx + 1,
+                );
+            }
+            _ => {}
+        }
+
+        match parse_match_arm(ast, next_token_idx) {
+            Ok((arm, after_body_token_idx)) => {
+                arms.push(arm);
+                // a `,` separates arms, a `}` closes the match
+                match ast
+                    .get_tokens()
+                    .find_next_non_blank_token(after_body_token_idx)
+                {
+                    Some((comma_token_idx, token))
+                        if token.get_kind() == &lexer::TokenKind::Comma =>
+                    {
+                        next_token_idx = comma_token_idx + 1;
+                    }
+                    Some((rcurlybrace_token_idx, token))
+                        if token.get_kind() == &lexer::TokenKind::RCurlyBrace =>
+                    {
+                        return finish_match(
+                            error,
+                            match_kw_token_idx,
+                            scrutinee_node_idx,
+                            arms,
+                            rcurlybrace_token_idx + 1,
+                        );
+                    }
+                    Some((error_token_idx, _)) => {
+                        error = error.add_new_error(ParseError::SingleParseError(
+                            SingleParseError::UnexpectedToken {
+                                msg: format!(
+                                    "expected `{}` or `{}` after a match arm",
+                                    lexer::TokenKind::Comma.get_string_repr(),
+                                    lexer::TokenKind::RCurlyBrace.get_string_repr()
+                                ),
+                                ctx_start_token_idx: lcurlybrace_token_idx,
+                                error_token_idx,
+                            },
+                        ));
+                        next_token_idx =
+                            find_recovery_idx(ast.get_tokens(), after_body_token_idx);
+                    }
+                    None => {
+                        error = error.add_new_error(ParseError::SingleParseError(
+                            SingleParseError::UnexpectedEof {
+                                msg: format!(
+                                    "no matching `{}`",
+                                    lexer::TokenKind::RCurlyBrace.get_string_repr()
+                                ),
+                                ctx_token_idx: lcurlybrace_token_idx,
+                            },
+                        ));
+                        break;
+                    }
+                }
+            }
+            Err(e) => {
+                error = error.add_new_error(e);
+                next_token_idx = find_recovery_idx(ast.get_tokens(), next_token_idx);
+            }
+        }
+    }
+
+    Err(error)
+}
+
+// assembles a `Match` node, or surfaces the accumulated per-arm errors if any arm failed
+fn finish_match(
+    error: ParseError,
+    match_kw_token_idx: TokenIdx,
+    scrutinee_node_idx: ast::AstNodeIdx,
+    arms: Vec<ast::MatchArm>,
+    next_token_idx: TokenIdx,
+) -> ParseResult {
+    if !matches!(error, ParseError::Empty) {
+        return Err(error);
+    }
+    ParseResult::new_node(
+        ast::AstNode::Expression(ast::Expr::Match {
+            match_kw: match_kw_token_idx,
+            scrutinee_node_idx,
+            arms,
+        }),
+        next_token_idx,
+    )
+}
+
+// parses a single `pattern => body` arm, returning the arm and the index past its body
+fn parse_match_arm(
+    ast: &mut ast::Ast<ParseError>,
+    start_search_idx: TokenIdx,
+) -> Result<(ast::MatchArm, TokenIdx), ParseError> {
+    let HappyPath::Node {
+        node: pattern,
+        next_token_idx: after_pattern_token_idx,
+    } = parse_pattern(ast, start_search_idx)?
+    else {
+        panic!("BUG: parse_pattern should always return a node")
+    };
+    let pattern_node_idx = ast.push_node(pattern);
+
+    let fat_arrow_token_idx = must_find(
+        ast.get_tokens(),
+        start_search_idx,
+        after_pattern_token_idx,
+        || {
+            format!(
+                "expected `{}` after a match pattern",
+                lexer::TokenKind::FatArrow.get_string_repr()
+            )
+        },
+        |token_kind| token_kind == &lexer::TokenKind::FatArrow,
+    )?;
+
+    // the body is a block `{ .. }` or a bare expression
+    let (body, after_body_token_idx) = match ast
+        .get_tokens()
+        .find_next_non_blank_token(fat_arrow_token_idx + 1)
+    {
+        Some((lcurlybrace_token_idx, token))
+            if token.get_kind() == &lexer::TokenKind::LCurlyBrace =>
+        {
+            let HappyPath::Node {
+                node,
+                next_token_idx,
+            } = parse_block_expression(ast, lcurlybrace_token_idx)?
+            else {
+                panic!("BUG: parse_block_expression should always return a node")
+            };
+            (node, next_token_idx)
+        }
+        _ => {
+            let no_body_err_fn = || "expected an expression for a match arm body";
+            let HappyPath::Node {
+                node,
+                next_token_idx,
+            } = parse_expression(ast, fat_arrow_token_idx + 1, Precedence::new(0))
+                .map_err(|e| e.add_error_context(no_body_err_fn()))?
+            else {
+                return Err(ParseError::SingleParseError(
+                    SingleParseError::UnexpectedEof {
+                        msg: no_body_err_fn().to_owned(),
+                        ctx_token_idx: fat_arrow_token_idx,
+                    },
+                ));
+            };
+            (node, next_token_idx)
+        }
+    };
+
+    Ok((
+        ast::MatchArm {
+            pattern_node_idx,
+            fat_arrow: fat_arrow_token_idx,
+            body_node_idx: ast.push_node(body),
+        },
+        after_body_token_idx,
+    ))
+}
+
+// recursively parses a pattern starting from the first non-blank token at or after
+// `start_search_idx`; reusable anywhere a binding pattern is expected (e.g. the LHS of a
+// future destructuring `let`)
+fn parse_pattern(ast: &mut ast::Ast<ParseError>, start_search_idx: TokenIdx) -> ParseResult {
+    let no_pattern_err_fn = || "expected a pattern";
+
+    let Some((pattern_start_token_idx, pattern_start_token)) =
+        ast.get_tokens().find_next_non_blank_token(start_search_idx)
+    else {
+        return ParseResult::new_error_unexpected_eof(no_pattern_err_fn(), start_search_idx);
+    };
+
+    match pattern_start_token.get_kind() {
+        // `_` lexes as an identifier, so the wildcard is detected by its spelling
+        lexer::TokenKind::Identifier { name } if name == "_" => ParseResult::new_node(
+            ast::AstNode::Pattern(ast::Pat::Wildcard {
+                token_idx: pattern_start_token_idx,
+            }),
+            pattern_start_token_idx + 1,
+        ),
+        lexer::TokenKind::Identifier { .. } => ParseResult::new_node(
+            ast::AstNode::Pattern(ast::Pat::Binding {
+                token_idx: pattern_start_token_idx,
+            }),
+            pattern_start_token_idx + 1,
+        ),
+        lexer::TokenKind::I64 => ParseResult::new_node(
+            ast::AstNode::Pattern(ast::Pat::Int {
+                token_idx: pattern_start_token_idx,
+            }),
+            pattern_start_token_idx + 1,
+        ),
+        lexer::TokenKind::StringLiteral { content } => ParseResult::new_node(
+            ast::AstNode::Pattern(ast::Pat::StringLiteral {
+                token_idx: pattern_start_token_idx,
+                content: content.clone(),
+            }),
+            pattern_start_token_idx + 1,
+        ),
+        lexer::TokenKind::LParen => parse_tuple_pattern(ast, pattern_start_token_idx),
+        _ => ParseResult::new_error_unexpected_token(
+            no_pattern_err_fn(),
+            start_search_idx,
+            pattern_start_token_idx,
+        ),
+    }
+}
+
+// first token is the `(` at `lparen_token_idx`
+fn parse_tuple_pattern(ast: &mut ast::Ast<ParseError>, lparen_token_idx: TokenIdx) -> ParseResult {
+    let mut elems = Vec::new();
+    let mut next_token_idx = lparen_token_idx + 1;
+    loop {
+        // empty tuple, or the `)` after the last element
+        if let Some((rparen_token_idx, rparen_token)) =
+            ast.get_tokens().find_next_non_blank_token(next_token_idx)
+        {
+            if rparen_token.get_kind() == &lexer::TokenKind::RParen {
+                return ParseResult::new_node(
+                    ast::AstNode::Pattern(ast::Pat::Tuple {
+                        lparen: lparen_token_idx,
+                        elems,
+                        rparen: rparen_token_idx,
+                    }),
+                    rparen_token_idx + 1,
+                );
+            }
+        }
+
+        let HappyPath::Node {
+            node: elem,
+            next_token_idx: after_elem_token_idx,
+        } = parse_pattern(ast, next_token_idx)?
+        else {
+            panic!("BUG: parse_pattern should always return a node")
+        };
+        elems.push(ast.push_node(elem));
+
+        let separator_token_idx = must_find(
+            ast.get_tokens(),
+            lparen_token_idx,
+            after_elem_token_idx,
+            || {
+                format!(
+                    "expected `{}` or `{}` after a tuple-pattern element",
+                    lexer::TokenKind::Comma.get_string_repr(),
+                    lexer::TokenKind::RParen.get_string_repr()
+                )
+            },
+            |token_kind| {
+                token_kind == &lexer::TokenKind::Comma || token_kind == &lexer::TokenKind::RParen
+            },
+        )?;
+        if ast.get_tokens()[separator_token_idx].get_kind() == &lexer::TokenKind::RParen {
+            return ParseResult::new_node(
+                ast::AstNode::Pattern(ast::Pat::Tuple {
+                    lparen: lparen_token_idx,
+                    elems,
+                    rparen: separator_token_idx,
+                }),
+                separator_token_idx + 1,
+            );
+        }
+        next_token_idx = separator_token_idx + 1;
+    }
+}
+
+fn parse_block_expression(
+    ast: &mut ast::Ast<ParseError>,
+    lcurlybrace_token_idx: TokenIdx,
+) -> ParseResult {
+    let mut statements_node_indices = Vec::with_capacity(25);
+    let mut tail_expression_node_idx = None;
+    let mut next_token_idx = lcurlybrace_token_idx + 1;
+    let mut error = ParseError::no_error();
+    loop {
+        match ast.get_tokens().find_next_non_blank_token(next_token_idx) {
+            Some((rcurlybrace_token_idx, token))
+                if token.get_kind() == &lexer::TokenKind::RCurlyBrace =>
+            {
+                return ParseResult::new_node(
+                    ast::AstNode::Expression(ast::Expr::Block {
+                        lcurlybracket: lcurlybrace_token_idx,
+                        statements_node_indices,
+                        tail_expression_node_idx,
+                        rcurlybracket: rcurlybrace_token_idx,
+                    }),
+                    rcurlybrace_token_idx + 1,
+                );
+            }
+            _ => {}
+        }
+
+        // the block owns the statement-vs-tail decision: a definition is always a statement,
+        // while any other expression becomes the block's tail value when it is followed by the
+        // closing `}` instead of a `;`
+        match parse_block_item(ast, lcurlybrace_token_idx, next_token_idx) {
+            Ok(BlockItem::Statement {
+                node: statement,
+                next_token_idx: token_idx,
+            }) => {
+                statements_node_indices.push(ast.push_node(statement));
+                next_token_idx = token_idx;
+            }
+            Ok(BlockItem::Tail {
+                node: tail,
+                next_token_idx: token_idx,
+            }) => {
+                tail_expression_node_idx = Some(ast.push_node(tail));
+                next_token_idx = token_idx;
+            }
+            Ok(BlockItem::Finished) => {
                 error = error.add_new_error(ParseError::SingleParseError(
                     SingleParseError::UnexpectedEof {
                         msg: format!(
@@ -651,6 +1627,92 @@ fn parse_block_expression(
     Err(error)
 }
 
+// a single item inside a block: a `;`-terminated statement, or a trailing expression that the
+// block yields as its value because it is immediately followed by the closing `}`
+enum BlockItem {
+    Statement { node: ast::AstNode, next_token_idx: TokenIdx },
+    Tail { node: ast::AstNode, next_token_idx: TokenIdx },
+    Finished,
+}
+
+fn parse_block_item(
+    ast: &mut ast::Ast<ParseError>,
+    lcurlybrace_token_idx: TokenIdx,
+    next_token_idx: TokenIdx,
+) -> Result<BlockItem, ParseError> {
+    let Some((item_start_token_idx, item_start_token)) =
+        find_start_of_non_empty_statement(ast, next_token_idx)
+    else {
+        return Ok(BlockItem::Finished);
+    };
+
+    // definitions are always statements and carry their own `;`
+    if matches!(
+        item_start_token.get_kind(),
+        lexer::TokenKind::KwLet | lexer::TokenKind::KwVar
+    ) {
+        let item_start_token = item_start_token.clone();
+        let HappyPath::Node {
+            node,
+            next_token_idx,
+        } = parse_definition_statement(ast, item_start_token_idx, &item_start_token)?
+        else {
+            panic!("BUG: parse_definition_statement should always return a node")
+        };
+        return Ok(BlockItem::Statement {
+            node,
+            next_token_idx,
+        });
+    }
+
+    let HappyPath::Node {
+        node,
+        next_token_idx: after_expr_token_idx,
+    } = parse_expression(ast, item_start_token_idx, Precedence::new(0))
+        .map_err(|e| e.add_error_context("this must be an expression"))?
+    else {
+        panic!("BUG: parse_expression should always return a node, empty statements must be filtered out before this")
+    };
+
+    // `;` closes a statement, `}` makes this expression the block's tail value
+    match ast
+        .get_tokens()
+        .find_next_non_blank_token(after_expr_token_idx)
+    {
+        Some((semicolon_token_idx, token))
+            if token.get_kind() == &lexer::TokenKind::SemiColon =>
+        {
+            Ok(BlockItem::Statement {
+                node: ast::AstNode::Statement(ast::Stat::Expression(ast.push_node(node))),
+                next_token_idx: semicolon_token_idx + 1,
+            })
+        }
+        Some((_, token)) if token.get_kind() == &lexer::TokenKind::RCurlyBrace => {
+            Ok(BlockItem::Tail {
+                node,
+                next_token_idx: after_expr_token_idx,
+            })
+        }
+        Some((error_token_idx, _)) => Err(ParseError::SingleParseError(
+            SingleParseError::UnexpectedToken {
+                msg: format!(
+                    "statement must end with `{}`",
+                    lexer::TokenKind::SemiColon.get_string_repr()
+                ),
+                ctx_start_token_idx: item_start_token_idx,
+                error_token_idx,
+            },
+        )),
+        None => Err(ParseError::SingleParseError(SingleParseError::UnexpectedEof {
+            msg: format!(
+                "no matching `{}`",
+                lexer::TokenKind::RCurlyBrace.get_string_repr()
+            ),
+            ctx_token_idx: lcurlybrace_token_idx,
+        })),
+    }
+}
+
 fn parse_if_expression(ast: &mut ast::Ast<ParseError>, if_kw_token_idx: TokenIdx) -> ParseResult {
     // codition expression
     let no_cond_expr_err_fn = || {
@@ -663,7 +1725,7 @@ fn parse_if_expression(ast: &mut ast::Ast<ParseError>, if_kw_token_idx: TokenIdx
     let HappyPath::Node {
         node: cond_expr,
         next_token_idx: after_cond_expr_token_idx,
-    } = parse_expression(ast, if_kw_token_idx + 1, Precedence::new(0))
+    } = parse_expression_in_ctx(ast, if_kw_token_idx + 1, Precedence::new(0), true)
         .map_err(|e| e.add_error_context(no_cond_expr_err_fn()))?
     else {
         return ParseResult::new_error_unexpected_eof(no_cond_expr_err_fn(), if_kw_token_idx);
@@ -800,17 +1862,167 @@ fn parse_if_expression(ast: &mut ast::Ast<ParseError>, if_kw_token_idx: TokenIdx
                 after_else_block_token_idx,
             )
         }
-        Some((error_token_idx, _)) => ParseResult::new_error_unexpected_token(
-            nothing_after_else_err_fn(),
-            else_kw_token_idx,
-            error_token_idx,
-        ),
+        // whatever follows `else` is neither `{` nor `if`; try to recover with an actionable
+        // suggestion instead of stopping dead (modeled on rustc's `parse_expected_else_block`)
+        Some((error_token_idx, error_token)) => {
+            let error_token_kind = error_token.get_kind().clone();
+            if !token_starts_expression(&error_token_kind) {
+                return ParseResult::new_error_unexpected_token(
+                    nothing_after_else_err_fn(),
+                    else_kw_token_idx,
+                    error_token_idx,
+                );
+            }
+
+            let HappyPath::Node {
+                node: body,
+                next_token_idx: after_body_token_idx,
+            } = parse_expression(ast, error_token_idx, Precedence::new(0))?
+            else {
+                return ParseResult::new_error_unexpected_eof(
+                    nothing_after_else_err_fn(),
+                    else_kw_token_idx,
+                );
+            };
+
+            // `else (cond) { .. }`: a parenthesized expression followed by `{` looks like a
+            // chained `else if` that dropped its `if`; recover as `else if` and say so
+            let followed_by_block = matches!(
+                ast.get_tokens().find_next_non_blank_token(after_body_token_idx),
+                Some((_, token)) if token.get_kind() == &lexer::TokenKind::LCurlyBrace
+            );
+            if followed_by_block
+                && matches!(body, ast::AstNode::Expression(ast::Expr::Grouped { .. }))
+            {
+                let (lcurlybrace_token_idx, _) = ast
+                    .get_tokens()
+                    .find_next_non_blank_token(after_body_token_idx)
+                    .expect("BUG: just checked a `{` follows the else condition");
+                if let Ok(HappyPath::Node {
+                    node: else_if_block,
+                    next_token_idx: after_else_if_block_token_idx,
+                }) = parse_block_expression(ast, lcurlybrace_token_idx)
+                {
+                    ast.accumulate_error(ParseError::new_single_error(
+                        SingleParseError::UnexpectedToken {
+                            msg: "expected `if` after `else`; add an `if` if this is the condition of a chained `else if` statement".to_owned(),
+                            ctx_start_token_idx: else_kw_token_idx,
+                            error_token_idx,
+                        },
+                    ));
+                    let else_if = ast::AstNode::Expression(ast::Expr::If {
+                        if_kw: error_token_idx,
+                        condition_node_idx: ast.push_node(body),
+                        then_block_node_idx: ast.push_node(else_if_block),
+                        else_kw: None,
+                        else_block_node_idx: None,
+                        if_node_idx: None,
+                    });
+                    return ParseResult::new_node(
+                        ast::AstNode::Expression(ast::Expr::If {
+                            if_kw: if_kw_token_idx,
+                            condition_node_idx: ast.push_node(cond_expr),
+                            then_block_node_idx: ast.push_node(curly_block),
+                            else_kw: Some(else_kw_token_idx),
+                            else_block_node_idx: None,
+                            if_node_idx: Some(ast.push_node(else_if)),
+                        }),
+                        after_else_if_block_token_idx,
+                    );
+                }
+            }
+
+            // otherwise `else <expr>`: the body is just missing its braces
+            ast.accumulate_error(ParseError::new_single_error(
+                SingleParseError::UnexpectedToken {
+                    msg: "expected `{` after `else`; add braces around the else body".to_owned(),
+                    ctx_start_token_idx: else_kw_token_idx,
+                    error_token_idx,
+                },
+            ));
+            ParseResult::new_node(
+                ast::AstNode::Expression(ast::Expr::If {
+                    if_kw: if_kw_token_idx,
+                    condition_node_idx: ast.push_node(cond_expr),
+                    then_block_node_idx: ast.push_node(curly_block),
+                    else_kw: Some(else_kw_token_idx),
+                    else_block_node_idx: Some(ast.push_node(body)),
+                    if_node_idx: None,
+                }),
+                after_body_token_idx,
+            )
+        }
         None => {
             ParseResult::new_error_unexpected_eof(nothing_after_else_err_fn(), else_kw_token_idx)
         }
     }
 }
 
+// the set of tokens that can begin an expression, used by `else` recovery to tell a brace-less
+// body (`else foo`) apart from genuine garbage (`else ;`)
+fn token_starts_expression(kind: &lexer::TokenKind) -> bool {
+    matches!(
+        kind,
+        lexer::TokenKind::Identifier { .. }
+            | lexer::TokenKind::I64
+            | lexer::TokenKind::StringLiteral { .. }
+            | lexer::TokenKind::LParen
+            | lexer::TokenKind::Minus
+    )
+}
+
+// a prefix operator binds tighter than any binary operator; recursing with this precedence makes
+// `-a * b` parse as `(-a) * b` while leaving binary operators to the caller's shift loop
+const PREFIX_PRECEDENCE: i64 = 100;
+
+// either returns error or a `Negation`/`Not` node
+//
+// first token is a registered prefix operator (`-` or `!`)
+fn parse_prefix_expression(
+    ast: &mut ast::Ast<ParseError>,
+    op_token_idx: TokenIdx,
+    op_token: &Token,
+) -> ParseResult {
+    // `-<integer literal>` takes the literal path: a `Negation` directly over the typed `Int` node
+    if op_token.get_kind() == &lexer::TokenKind::Minus
+        && matches!(
+            ast.get_tokens().find_next_non_blank_token(op_token_idx + 1),
+            Some((_, num_token)) if num_token.get_kind() == &lexer::TokenKind::I64
+        )
+    {
+        return must_be_i64_after_dash_sign(ast, op_token_idx + 1, op_token_idx);
+    }
+
+    let no_operand_err_fn = || {
+        format!(
+            "expected an expression after `{}`",
+            op_token.get_kind().get_string_repr()
+        )
+    };
+    let HappyPath::Node {
+        node: operand,
+        next_token_idx: after_operand_token_idx,
+    } = parse_expression(ast, op_token_idx + 1, Precedence::new(PREFIX_PRECEDENCE))
+        .map_err(|e| e.add_error_context(no_operand_err_fn()))?
+    else {
+        return ParseResult::new_error_unexpected_eof(no_operand_err_fn(), op_token_idx);
+    };
+
+    let operand_node_idx = ast.push_node(operand);
+    let node = match op_token.get_kind() {
+        lexer::TokenKind::Minus => ast::Expr::Negation {
+            operator: op_token_idx,
+            operand: operand_node_idx,
+        },
+        lexer::TokenKind::Not => ast::Expr::Not {
+            operator: op_token_idx,
+            operand: operand_node_idx,
+        },
+        _ => unreachable!("BUG: parse_prefix_expression called on a non-prefix operator"),
+    };
+    ParseResult::new_node(ast::AstNode::Expression(node), after_operand_token_idx)
+}
+
 /// parse_primary parses a primary expression.
 ///
 /// # Note
@@ -821,10 +2033,17 @@ fn parse_primary(
     start_token: &Token,
 ) -> ParseResult {
     match start_token.get_kind() {
-        lexer::TokenKind::I64 => ParseResult::new_node(
-            ast::AstNode::Expression(ast::Expr::I64(start_token_idx)),
-            start_token_idx + 1,
-        ),
+        lexer::TokenKind::I64 => {
+            let (bits, signed) = int_literal_type(ast.get_token_str(start_token_idx));
+            ParseResult::new_node(
+                ast::AstNode::Expression(ast::Expr::Int {
+                    token_idx: start_token_idx,
+                    bits,
+                    signed,
+                }),
+                start_token_idx + 1,
+            )
+        }
         lexer::TokenKind::Identifier { .. } => ParseResult::new_node(
             ast::AstNode::Expression(ast::Expr::Identifier(start_token_idx)),
             start_token_idx + 1,
@@ -834,6 +2053,9 @@ fn parse_primary(
             must_be_i64_after_dash_sign(ast, start_token_idx + 1, start_token_idx)
         }
         lexer::TokenKind::LParen => must_be_paren_expression(ast, start_token_idx),
+        // a block is itself an expression, so it can start one anywhere (e.g. `let x = { .. };`),
+        // not only as the body of an `if`/`while`/`loop`/`match`
+        lexer::TokenKind::LCurlyBrace => parse_block_expression(ast, start_token_idx),
         lexer::TokenKind::StringLiteral { content } => ParseResult::new_node(
             ast::AstNode::Expression(ast::Expr::StringLiteral {
                 token_idx: start_token_idx,
@@ -841,11 +2063,24 @@ fn parse_primary(
             }),
             start_token_idx + 1,
         ),
-        _ => ParseResult::new_error_unexpected_token(
-            "expected an expression",
-            start_token_idx,
-            start_token_idx,
-        ),
+        // not a valid expression start: plant an `Expr::Error` hole, record the diagnostic, and
+        // resynchronize so the rest of the input is still parsed and reported
+        _ => {
+            ast.accumulate_error(ParseError::new_single_error(
+                SingleParseError::UnexpectedToken {
+                    msg: "expected an expression".to_owned(),
+                    ctx_start_token_idx: start_token_idx,
+                    error_token_idx: start_token_idx,
+                },
+            ));
+            let sync_token_idx = recover_to_sync_point(ast.get_tokens(), start_token_idx);
+            ParseResult::new_node(
+                ast::AstNode::Expression(ast::Expr::Error {
+                    span_token_idx: start_token_idx,
+                }),
+                sync_token_idx,
+            )
+        }
     }
 }
 
@@ -878,12 +2113,24 @@ fn must_be_paren_expression(
         .get_tokens()
         .find_next_non_blank_token(after_expr_token_idx)
     else {
-        return ParseResult::new_error_unexpected_eof(
-            format!(
-                "no matching `{}`",
-                lexer::TokenKind::RParen.get_string_repr()
-            ),
-            lparen_token_idx,
+        // end of input before the closing `)`: record the diagnostic and synthesize the missing
+        // paren so the surrounding parse can still finish
+        ast.accumulate_error(ParseError::new_single_error(
+            SingleParseError::UnexpectedEof {
+                msg: format!(
+                    "no matching `{}`",
+                    lexer::TokenKind::RParen.get_string_repr()
+                ),
+                ctx_token_idx: lparen_token_idx,
+            },
+        ));
+        return ParseResult::new_node(
+            ast::AstNode::Expression(ast::Expr::Grouped {
+                lparen: lparen_token_idx,
+                expression_node_idx: ast.push_node(expr),
+                rparen: after_expr_token_idx,
+            }),
+            after_expr_token_idx,
         );
     };
 
@@ -897,10 +2144,139 @@ fn must_be_paren_expression(
             rparen_token_idx + 1,
         )
     } else {
-        ParseResult::new_error_mismatched_paren(lparen_token_idx, rparen_token_idx)
+        // the expression is terminated by something other than `)`: record the mismatch and
+        // synthesize the missing `)` at this position, leaving the offending token for the caller
+        // to resynchronize on instead of aborting the whole parse
+        ast.accumulate_error(ParseError::new_single_error(
+            SingleParseError::MismatchedParentheses {
+                lparen: lparen_token_idx,
+                error_token_idx: rparen_token_idx,
+            },
+        ));
+        ParseResult::new_node(
+            ast::AstNode::Expression(ast::Expr::Grouped {
+                lparen: lparen_token_idx,
+                expression_node_idx: ast.push_node(expr),
+                rparen: rparen_token_idx,
+            }),
+            rparen_token_idx,
+        )
     }
 }
 
+// either returns a `Call` expression or an error
+//
+// first token is `(`, which immediately follows the callee expression
+fn parse_call_expression(
+    ast: &mut ast::Ast<ParseError>,
+    callee_node_idx: ast::AstNodeIdx,
+    lparen_token_idx: TokenIdx,
+) -> ParseResult {
+    let mut args = Vec::new();
+    let mut next_token_idx = lparen_token_idx + 1;
+    loop {
+        // empty list, or a trailing `)` after the last argument
+        if let Some((rparen_token_idx, rparen_token)) =
+            ast.get_tokens().find_next_non_blank_token(next_token_idx)
+        {
+            if rparen_token.get_kind() == &lexer::TokenKind::RParen {
+                return ParseResult::new_node(
+                    ast::AstNode::Expression(ast::Expr::Call {
+                        callee: callee_node_idx,
+                        lparen: lparen_token_idx,
+                        args,
+                        rparen: rparen_token_idx,
+                    }),
+                    rparen_token_idx + 1,
+                );
+            }
+        }
+
+        let no_arg_err_fn = || "expected an argument expression";
+        let HappyPath::Node {
+            node: arg,
+            next_token_idx: after_arg_token_idx,
+        } = parse_expression(ast, next_token_idx, Precedence::new(0))
+            .map_err(|e| e.add_error_context(no_arg_err_fn()))?
+        else {
+            return ParseResult::new_error_unexpected_eof(no_arg_err_fn(), lparen_token_idx);
+        };
+        args.push(ast.push_node(arg));
+
+        // a `,` continues the list, a `)` closes it
+        let separator_token_idx = must_find(
+            ast.get_tokens(),
+            lparen_token_idx,
+            after_arg_token_idx,
+            || {
+                format!(
+                    "expected `{}` or `{}` after an argument",
+                    lexer::TokenKind::Comma.get_string_repr(),
+                    lexer::TokenKind::RParen.get_string_repr()
+                )
+            },
+            |token_kind| {
+                token_kind == &lexer::TokenKind::Comma
+                    || token_kind == &lexer::TokenKind::RParen
+            },
+        )?;
+        if ast.get_tokens()[separator_token_idx].get_kind() == &lexer::TokenKind::RParen {
+            return ParseResult::new_node(
+                ast::AstNode::Expression(ast::Expr::Call {
+                    callee: callee_node_idx,
+                    lparen: lparen_token_idx,
+                    args,
+                    rparen: separator_token_idx,
+                }),
+                separator_token_idx + 1,
+            );
+        }
+        next_token_idx = separator_token_idx + 1;
+    }
+}
+
+// either returns an `Index` expression or an error
+//
+// first token is `[`, which immediately follows the base expression
+fn parse_index_expression(
+    ast: &mut ast::Ast<ParseError>,
+    base_node_idx: ast::AstNodeIdx,
+    lbracket_token_idx: TokenIdx,
+) -> ParseResult {
+    let no_index_err_fn = || "expected an index expression";
+    let HappyPath::Node {
+        node: index,
+        next_token_idx: after_index_token_idx,
+    } = parse_expression(ast, lbracket_token_idx + 1, Precedence::new(0))
+        .map_err(|e| e.add_error_context(no_index_err_fn()))?
+    else {
+        return ParseResult::new_error_unexpected_eof(no_index_err_fn(), lbracket_token_idx);
+    };
+
+    let rbracket_token_idx = must_find(
+        ast.get_tokens(),
+        lbracket_token_idx,
+        after_index_token_idx,
+        || {
+            format!(
+                "no matching `{}`",
+                lexer::TokenKind::RBracket.get_string_repr()
+            )
+        },
+        |token_kind| token_kind == &lexer::TokenKind::RBracket,
+    )?;
+
+    ParseResult::new_node(
+        ast::AstNode::Expression(ast::Expr::Index {
+            base: base_node_idx,
+            lbracket: lbracket_token_idx,
+            index: ast.push_node(index),
+            rbracket: rbracket_token_idx,
+        }),
+        rbracket_token_idx + 1,
+    )
+}
+
 pub(super) fn get_lex_errors(tokens: &lexer::Tokens) -> Option<SingleParseError> {
     let invalid_errors = tokens
         .iter()
@@ -1,10 +1,11 @@
 use pretty_assertions::assert_eq;
 use std::fs;
+use std::path::Path;
 use std::process::Command;
 
 const TEST_DATA_DIR: &str = "tests/file_test/testdata";
 
-fn check_error_msg(source_path: &std::path::Path, expected: &str) {
+fn run_llangc(source_path: &Path) -> String {
     let output = Command::new("cargo")
         .args([
             "run",
@@ -14,17 +15,167 @@ fn check_error_msg(source_path: &std::path::Path, expected: &str) {
             "--",
             source_path.to_str().unwrap(),
         ])
+        // keep the subprocess diagnostics plain so the harness can scan them without stripping
+        // ANSI escapes
+        .env("NO_COLOR", "1")
         .output()
         .expect("Failed to execute command");
-    let got = String::from_utf8(output.stderr).unwrap_or_default();
+    String::from_utf8(output.stderr).unwrap_or_default()
+}
+
+fn check_error_msg(source_path: &Path, expected: &str) {
     assert_eq!(
-        got,
+        run_llangc(source_path),
         expected,
         "Failed to compare `{}`",
         source_path.display()
     );
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DiagKind {
+    Error,
+    Warning,
+}
+
+impl DiagKind {
+    // parses the `ERROR`/`WARNING` word in an annotation, or the `error`/`warning` category in a
+    // rendered header; returns `None` for anything else
+    fn parse(word: &str) -> Option<Self> {
+        match word {
+            "ERROR" | "error" => Some(Self::Error),
+            "WARNING" | "warning" => Some(Self::Warning),
+            _ => None,
+        }
+    }
+}
+
+// a single `#~` expectation recovered from the source
+#[derive(Debug)]
+struct Expectation {
+    line: usize,
+    kind: DiagKind,
+    substring: String,
+}
+
+// a diagnostic recovered from the rendered `llangc` output
+#[derive(Debug)]
+struct EmittedDiag {
+    line: usize,
+    kind: DiagKind,
+    message: String,
+}
+
+// compiletest's inline annotations, adapted to this language's `#` line comments: a `#~`
+// comment attaches an expectation to a line, `~` to the comment's own line, each extra `^`
+// moving one line up, and `|` reusing the line of the previous annotation.
+fn scan_annotations(source: &str) -> Vec<Expectation> {
+    let mut expectations = Vec::new();
+    let mut last_line: Option<usize> = None;
+
+    for (idx, raw) in source.lines().enumerate() {
+        let Some(rest) = raw.find("#~").map(|at| &raw[at + 2..]) else {
+            continue;
+        };
+        let current = idx + 1;
+
+        let (line, rest) = if let Some(rest) = rest.strip_prefix('|') {
+            let line = last_line.expect("`#~|` with no preceding annotation");
+            (line, rest)
+        } else {
+            let carets = rest.chars().take_while(|&c| c == '^').count();
+            (current - carets, &rest[carets..])
+        };
+        last_line = Some(line);
+
+        let mut words = rest.split_whitespace();
+        let kind = words
+            .next()
+            .and_then(DiagKind::parse)
+            .unwrap_or_else(|| panic!("annotation on line {} has no ERROR/WARNING kind", current));
+        let substring = words.collect::<Vec<_>>().join(" ");
+        expectations.push(Expectation {
+            line,
+            kind,
+            substring,
+        });
+    }
+    expectations
+}
+
+// pulls the 1-based line number out of a rendered gutter line such as `    4|let x = 1;`; the
+// caret row carries an empty gutter (`     |`) and yields `None`
+fn gutter_line_nr(line: &str) -> Option<usize> {
+    let rest = line.trim_start();
+    let digits: String = rest.chars().take_while(char::is_ascii_digit).collect();
+    let after = &rest[digits.len()..];
+    if digits.is_empty() || !after.starts_with('|') {
+        return None;
+    }
+    digits.parse().ok()
+}
+
+// parses a header line like `stdin: error: unexpected token`, returning the kind and message;
+// context notes (`context: ...`) and gutter lines return `None`
+fn parse_header(line: &str) -> Option<(DiagKind, String)> {
+    for cate in ["error", "warning"] {
+        if let Some(at) = line.find(&format!(": {}: ", cate)) {
+            let kind = DiagKind::parse(cate).unwrap();
+            let message = line[at + cate.len() + 4..].to_string();
+            return Some((kind, message));
+        }
+    }
+    None
+}
+
+// a diagnostic block is a header line followed by its gutter rows; the diagnostic's line is the
+// first numbered gutter row after the header
+fn scan_diagnostics(rendered: &str) -> Vec<EmittedDiag> {
+    let mut diags = Vec::new();
+    let mut pending: Option<(DiagKind, String)> = None;
+    for line in rendered.lines() {
+        if let Some(header) = parse_header(line) {
+            pending = Some(header);
+        } else if let (Some((kind, message)), Some(nr)) = (&pending, gutter_line_nr(line)) {
+            diags.push(EmittedDiag {
+                line: nr,
+                kind: *kind,
+                message: message.clone(),
+            });
+            pending = None;
+        }
+    }
+    diags
+}
+
+fn check_annotations(source_path: &Path, source: &str) {
+    let expectations = scan_annotations(source);
+    let rendered = run_llangc(source_path);
+    let mut diags = scan_diagnostics(&rendered);
+
+    let mut unmatched = Vec::new();
+    for exp in &expectations {
+        let found = diags.iter().position(|d| {
+            d.line == exp.line && d.kind == exp.kind && d.message.contains(&exp.substring)
+        });
+        match found {
+            Some(i) => {
+                diags.swap_remove(i);
+            }
+            None => unmatched.push(exp),
+        }
+    }
+
+    assert!(
+        unmatched.is_empty() && diags.is_empty(),
+        "`{}`:\n  unmatched expectations: {:?}\n  unexpected diagnostics: {:?}\n--- rendered ---\n{}",
+        source_path.display(),
+        unmatched,
+        diags,
+        rendered,
+    );
+}
+
 #[test]
 fn parse_error_tests() {
     let mut entries = fs::read_dir(TEST_DATA_DIR)
@@ -36,11 +187,18 @@ fn parse_error_tests() {
     for entry in entries {
         let source_path = entry.path();
         if source_path.is_file() && source_path.extension().unwrap_or_default() == "llang" {
-            let expected_path = source_path.with_extension("expected");
-            check_error_msg(
-                &source_path,
-                &fs::read_to_string(expected_path).unwrap_or_default(),
-            );
+            let source = fs::read_to_string(&source_path).unwrap_or_default();
+            // a file carrying inline `#~` annotations is checked per-line; otherwise fall back to
+            // the sibling `.expected` golden file
+            if source.contains("#~") {
+                check_annotations(&source_path, &source);
+            } else {
+                let expected_path = source_path.with_extension("expected");
+                check_error_msg(
+                    &source_path,
+                    &fs::read_to_string(expected_path).unwrap_or_default(),
+                );
+            }
         }
     }
 }